@@ -1,9 +1,12 @@
-use crate::cost::{self, TokenUsage};
+use crate::budget::Gauge;
+use crate::cost::{self, ModelUsage, SessionRef, TokenUsage};
 use crate::detector::{DetectionResult, Status};
-use crate::tmux::{self, Pane};
+use crate::profile;
+use crate::state;
+use crate::mux::{self, Pane};
 use anyhow::Result;
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
@@ -15,8 +18,12 @@ pub struct PaneState {
     pub previous_status: Option<Status>,
     // Stats tracking
     pub stats: PaneStats,
-    // Token usage (fetched on demand with '$' key)
+    // Token usage (updated incrementally on each refresh)
     pub tokens: Option<TokenUsage>,
+    /// Token usage is being loaded in the background
+    pub tokens_loading: bool,
+    /// Transcript the token usage was read from
+    pub transcript: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -35,6 +42,46 @@ impl PaneState {
     pub fn status_duration_str(&self) -> String {
         format_duration(self.status_duration())
     }
+
+    /// Claude Code profile, as published by hooks or from the transcript's location
+    pub fn profile(&self) -> Option<String> {
+        self.pane.agent_profile.clone().or_else(|| {
+            self.transcript
+                .as_deref()
+                .and_then(profile::for_path)
+                .map(|p| p.name.clone())
+        })
+    }
+
+    /// Heading the pane is grouped under: its session, prefixed with
+    /// the profile when several are configured
+    pub fn group_label(&self) -> String {
+        match self.profile() {
+            Some(profile) if profile::has_several() => format!("{} · {}", profile, self.pane.session_name),
+            _ => self.pane.session_name.clone(),
+        }
+    }
+
+    /// Accumulated stats including time spent in the current state
+    pub fn time_in_states(&self) -> PaneStats {
+        let mut stats = self.stats.clone();
+        let current_secs = self.status_changed_at.elapsed().as_secs();
+        match self.status.status {
+            Status::Working => stats.total_working_secs += current_secs,
+            Status::WaitingForInput => stats.total_waiting_secs += current_secs,
+            Status::PermissionRequired => stats.total_permission_secs += current_secs,
+            Status::NotDetected => {}
+        }
+        stats
+    }
+}
+
+/// Provider of a pane if we can read its token usage
+fn usage_provider(pane: &Pane) -> Option<String> {
+    pane.agent_provider
+        .as_deref()
+        .filter(|p| cost::supports_usage(p))
+        .map(|p| p.to_string())
 }
 
 pub fn format_duration(d: Duration) -> String {
@@ -58,22 +105,39 @@ pub struct App {
     pub collapsed_sessions: HashSet<String>,
     pub status_filter: Option<Status>,
     pub self_pane_id: Option<String>,
+    pub toast: Option<Toast>,
+    /// Whether the first pane listing has arrived
+    pub panes_loaded: bool,
+    /// Usage against budget limits, shown in the header
+    pub budget_gauges: Vec<Gauge>,
+}
+
+/// How long a toast message stays in the footer
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// Short-lived message shown in place of the footer help
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub is_error: bool,
+    pub created: Instant,
 }
 
 #[derive(Debug, Clone)]
 pub struct StateChangeNotification {
     pub pane_name: String,
     pub folder_name: String,
-    pub session_name: String,
-    pub window_index: u32,
-    pub pane_index: u32,
+    /// Pane to jump to
+    pub pane: Pane,
     pub is_permission: bool,
+    /// @agent_provider of the pane
+    pub provider: Option<String>,
 }
 
 impl App {
     pub fn new(_capture_lines: usize, show_all: bool, compact: bool) -> Self {
         // Get our own pane ID to exclude from monitoring
-        let self_pane_id = std::env::var("TMUX_PANE").ok();
+        let self_pane_id = mux::active().current_pane_id();
 
         Self {
             pane_states: HashMap::new(),
@@ -85,16 +149,25 @@ impl App {
             collapsed_sessions: HashSet::new(),
             status_filter: None,
             self_pane_id,
+            toast: None,
+            panes_loaded: false,
+            budget_gauges: Vec::new(),
         }
     }
 
-    /// Refresh pane states from tmux
+    /// Refresh pane states from the multiplexer and the agent state directory
     ///
-    /// This is now a single cheap tmux list-panes call that reads
-    /// hook-published @agent_status and @agent_task options.
-    /// No screen scraping, no file parsing, no subprocess calls.
+    /// With tmux, a single cheap list-panes call reads hook-published
+    /// @agent_status and @agent_task options; the hooks' state files cover
+    /// other multiplexers and agents outside any. No transcript parsing.
     pub fn refresh(&mut self) -> Result<Vec<StateChangeNotification>> {
-        let panes = tmux::list_panes()?;
+        let panes = state::list_panes()?;
+        Ok(self.apply_panes(panes))
+    }
+
+    /// Update pane states from a pane listing (see `loader::Loader::request_panes`)
+    pub fn apply_panes(&mut self, panes: Vec<Pane>) -> Vec<StateChangeNotification> {
+        self.panes_loaded = true;
 
         // Track which panes we've seen
         let mut seen_ids: Vec<String> = Vec::new();
@@ -150,10 +223,9 @@ impl App {
                         notifications.push(StateChangeNotification {
                             pane_name: pane.display_name(),
                             folder_name: folder_name.clone(),
-                            session_name: pane.session_name.clone(),
-                            window_index: pane.window_index,
-                            pane_index: pane.pane_index,
+                            pane: pane.clone(),
                             is_permission: status.status == Status::PermissionRequired,
+                            provider: pane.agent_provider.clone(),
                         });
                     }
                     existing.previous_status = Some(existing.status.status);
@@ -173,6 +245,8 @@ impl App {
                         previous_status: None,
                         stats: PaneStats::default(),
                         tokens: None,
+                        tokens_loading: false,
+                        transcript: None,
                     },
                 );
             }
@@ -187,7 +261,7 @@ impl App {
             self.selected_index = visible_count - 1;
         }
 
-        Ok(notifications)
+        notifications
     }
 
     pub fn visible_panes(&self) -> Vec<&PaneState> {
@@ -199,9 +273,15 @@ impl App {
                 Some(filter) => p.status.status == filter,
                 None => true,
             })
+            // With --profile, hide Claude panes of other profiles
+            .filter(|p| match (profile::selected(), p.profile()) {
+                (Some(selected), Some(profile)) => selected.name == profile,
+                _ => true,
+            })
             .collect();
 
-        // Sort by status (Permission first, then Working), then by session/window/pane
+        // Sort by status (Permission first, then Working), then by session/window/pane;
+        // the grouped view keeps each group together first
         panes.sort_by(|a, b| {
             let status_order = |s: Status| match s {
                 Status::PermissionRequired => 0,
@@ -210,8 +290,14 @@ impl App {
                 Status::NotDetected => 3,
             };
 
-            status_order(a.status.status)
-                .cmp(&status_order(b.status.status))
+            let group_order = if self.group_by_session {
+                a.group_label().cmp(&b.group_label())
+            } else {
+                std::cmp::Ordering::Equal
+            };
+
+            group_order
+                .then(status_order(a.status.status).cmp(&status_order(b.status.status)))
                 .then(a.pane.session_name.cmp(&b.pane.session_name))
                 .then(a.pane.window_index.cmp(&b.pane.window_index))
                 .then(a.pane.pane_index.cmp(&b.pane.pane_index))
@@ -278,19 +364,17 @@ impl App {
         let mut stats = AggregatedStats::default();
 
         for pane in &panes {
-            // Add accumulated stats
-            stats.total_working_secs += pane.stats.total_working_secs;
-            stats.total_waiting_secs += pane.stats.total_waiting_secs;
-            stats.total_permission_secs += pane.stats.total_permission_secs;
-            stats.total_state_changes += pane.stats.state_changes;
-
-            // Add current state time
-            let current_secs = pane.status_changed_at.elapsed().as_secs();
-            match pane.status.status {
-                Status::Working => stats.total_working_secs += current_secs,
-                Status::WaitingForInput => stats.total_waiting_secs += current_secs,
-                Status::PermissionRequired => stats.total_permission_secs += current_secs,
-                Status::NotDetected => {}
+            // Accumulated stats plus current state time
+            let times = pane.time_in_states();
+            stats.total_working_secs += times.total_working_secs;
+            stats.total_waiting_secs += times.total_waiting_secs;
+            stats.total_permission_secs += times.total_permission_secs;
+            stats.total_state_changes += times.state_changes;
+
+            if let Some(ref tokens) = pane.tokens {
+                for (model, usage) in &tokens.by_model {
+                    stats.by_model.entry(model.clone()).or_default().add(usage);
+                }
             }
         }
 
@@ -306,15 +390,60 @@ impl App {
         }
     }
 
-    /// Refresh token usage and costs for all Claude panes
+    /// Refresh token usage and costs for all panes with readable transcripts
     pub fn refresh_costs(&mut self) {
+        let mut tracker = cost::UsageTracker::default();
         for pane_state in self.pane_states.values_mut() {
-            // Only fetch for Claude sessions
-            if pane_state.pane.agent_provider.as_deref() == Some("claude") {
-                let usage = cost::get_claude_usage(&pane_state.pane.current_path);
+            if let Some(provider) = usage_provider(&pane_state.pane) {
+                let (transcript, usage) =
+                    tracker.session_usage(&provider, &SessionRef::from_pane(&pane_state.pane));
                 pane_state.tokens = Some(usage);
+                pane_state.transcript = transcript;
+            }
+        }
+    }
+
+    /// Mark panes as loading and return (pane id, provider, session) to load
+    pub fn begin_cost_refresh(&mut self) -> Vec<(String, String, SessionRef)> {
+        let mut requests = Vec::new();
+        for pane_state in self.pane_states.values_mut() {
+            if pane_state.tokens_loading {
+                continue;
+            }
+            if let Some(provider) = usage_provider(&pane_state.pane) {
+                pane_state.tokens_loading = true;
+                requests.push((
+                    pane_state.pane.id.clone(),
+                    provider,
+                    SessionRef::from_pane(&pane_state.pane),
+                ));
             }
         }
+        requests
+    }
+
+    /// Store token usage loaded in the background
+    pub fn apply_usage(&mut self, pane_id: &str, transcript: Option<PathBuf>, usage: TokenUsage) {
+        if let Some(pane_state) = self.pane_states.get_mut(pane_id) {
+            pane_state.tokens = Some(usage);
+            pane_state.tokens_loading = false;
+            pane_state.transcript = transcript;
+        }
+    }
+
+    pub fn show_toast(&mut self, message: String, is_error: bool) {
+        self.toast = Some(Toast {
+            message,
+            is_error,
+            created: Instant::now(),
+        });
+    }
+
+    /// Current toast, if it hasn't expired yet
+    pub fn active_toast(&self) -> Option<&Toast> {
+        self.toast
+            .as_ref()
+            .filter(|t| t.created.elapsed() < TOAST_DURATION)
     }
 
     pub fn selected_pane(&self) -> Option<&PaneState> {
@@ -357,6 +486,8 @@ pub struct AggregatedStats {
     pub total_waiting_secs: u64,
     pub total_permission_secs: u64,
     pub total_state_changes: u32,
+    /// Token usage across panes by model
+    pub by_model: BTreeMap<String, ModelUsage>,
 }
 
 impl AggregatedStats {
@@ -369,93 +500,3 @@ impl AggregatedStats {
         }
     }
 }
-
-#[derive(Serialize)]
-pub struct ExportData {
-    pub timestamp: String,
-    pub summary: ExportSummary,
-    pub panes: Vec<ExportPane>,
-}
-
-#[derive(Serialize)]
-pub struct ExportSummary {
-    pub total_panes: usize,
-    pub total_working_secs: u64,
-    pub total_waiting_secs: u64,
-    pub total_permission_secs: u64,
-    pub total_state_changes: u32,
-    pub efficiency_percent: f64,
-}
-
-#[derive(Serialize)]
-pub struct ExportPane {
-    pub session: String,
-    pub window: u32,
-    pub pane: u32,
-    pub path: String,
-    pub current_status: String,
-    pub task: Option<String>,
-    pub working_secs: u64,
-    pub waiting_secs: u64,
-    pub permission_secs: u64,
-    pub state_changes: u32,
-}
-
-impl App {
-    pub fn export_stats(&self) -> ExportData {
-        let stats = self.aggregated_stats();
-        let panes = self.visible_panes();
-
-        let export_panes: Vec<ExportPane> = panes
-            .iter()
-            .map(|p| {
-                let current_secs = p.status_changed_at.elapsed().as_secs();
-                let mut working = p.stats.total_working_secs;
-                let mut waiting = p.stats.total_waiting_secs;
-                let mut permission = p.stats.total_permission_secs;
-
-                // Add current state time
-                match p.status.status {
-                    Status::Working => working += current_secs,
-                    Status::WaitingForInput => waiting += current_secs,
-                    Status::PermissionRequired => permission += current_secs,
-                    Status::NotDetected => {}
-                }
-
-                ExportPane {
-                    session: p.pane.session_name.clone(),
-                    window: p.pane.window_index,
-                    pane: p.pane.pane_index,
-                    path: p.pane.current_path.clone(),
-                    current_status: p.status.status.label().to_string(),
-                    task: p.status.task.clone(),
-                    working_secs: working,
-                    waiting_secs: waiting,
-                    permission_secs: permission,
-                    state_changes: p.stats.state_changes,
-                }
-            })
-            .collect();
-
-        ExportData {
-            timestamp: chrono_lite_now(),
-            summary: ExportSummary {
-                total_panes: stats.pane_count,
-                total_working_secs: stats.total_working_secs,
-                total_waiting_secs: stats.total_waiting_secs,
-                total_permission_secs: stats.total_permission_secs,
-                total_state_changes: stats.total_state_changes,
-                efficiency_percent: stats.efficiency_percent(),
-            },
-            panes: export_panes,
-        }
-    }
-}
-
-fn chrono_lite_now() -> String {
-    use std::time::SystemTime;
-    let duration = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}", duration.as_secs())
-}
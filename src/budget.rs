@@ -1,8 +1,21 @@
+use crate::config;
+use crate::cost::{self, ModelUsage, UsageEntry};
+use crate::export::{csv_field, md_cell};
+use crate::hook;
+use crate::hooks;
+use crate::profile::{self, Profile};
+use crate::providers::{claude, codex};
+use crate::usage_index::{self, IndexKind, UsageIndex};
 use anyhow::{Context, Result};
-use clap::Subcommand;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use clap::{Args, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Subcommand, Debug)]
 pub enum BudgetAction {
@@ -10,32 +23,63 @@ pub enum BudgetAction {
     Status,
 
     /// Set budget limits
-    Set {
-        /// Daily token limit (e.g., 100k, 1m)
-        #[arg(long)]
-        daily: Option<String>,
+    Set(LimitArgs),
+
+    /// Show detailed usage report
+    Report {
+        /// Number of calendar days to include, today included
+        #[arg(short, long, default_value = "7")]
+        days: u32,
 
-        /// Weekly token limit
+        /// Group by one of these, or two separated by a comma (e.g. day,model)
+        #[arg(short, long, value_delimiter = ',', default_value = "day")]
+        group_by: Vec<Grouping>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+
+    /// Enforce limits in Claude Code through a hook on each prompt
+    Enforce {
+        /// off: only report usage; warn: warn once a limit is passed; block: refuse prompts
+        mode: Enforcement,
+
+        /// Only for this project directory or glob, overriding the profile's mode
         #[arg(long)]
-        weekly: Option<String>,
+        project: Option<String>,
 
-        /// Monthly token limit
+        /// Also check before each tool call, stopping a turn that runs over
         #[arg(long)]
-        monthly: Option<String>,
+        tools: bool,
+
+        /// Warn from this percentage of a limit on (a soft limit)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        warn_at: Option<u8>,
+
+        /// Apply the settings change without asking
+        #[arg(short, long)]
+        yes: bool,
     },
 
-    /// Show detailed usage report
-    Report {
-        /// Number of days to include
-        #[arg(short, long, default_value = "7")]
-        days: u32,
+    /// Pause enforcement, e.g. to finish urgent work over the limit
+    Bypass {
+        /// How long to pause for
+        #[arg(long, default_value = "60")]
+        minutes: u32,
 
-        /// Group by: day, project, session
-        #[arg(short, long, default_value = "day")]
-        group_by: String,
+        /// End the pause now
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Check usage against limits (run by the budget hook)
+    Check {
+        /// Hook event: UserPromptSubmit or PreToolUse
+        event: String,
     },
 
-    /// Reset usage counters
+    /// Reset the profile's limits and enforcement, removing the budget hook
     Reset {
         /// Confirm reset
         #[arg(long)]
@@ -43,65 +87,424 @@ pub enum BudgetAction {
     },
 }
 
+#[derive(Args, Debug)]
+pub struct LimitArgs {
+    /// Limit only usage in this project directory or glob (e.g. "~/work/client-a/*")
+    #[arg(long)]
+    project: Option<String>,
+
+    /// Remove the project's limits
+    #[arg(long, requires = "project")]
+    remove: bool,
+
+    /// Daily token limit (e.g., 100k, 1m)
+    #[arg(long)]
+    daily: Option<String>,
+
+    /// Weekly token limit
+    #[arg(long)]
+    weekly: Option<String>,
+
+    /// Monthly token limit
+    #[arg(long)]
+    monthly: Option<String>,
+
+    /// Daily spending limit in USD (e.g., 5, $12.50)
+    #[arg(long)]
+    daily_usd: Option<String>,
+
+    /// Weekly spending limit in USD
+    #[arg(long)]
+    weekly_usd: Option<String>,
+
+    /// Monthly spending limit in USD
+    #[arg(long)]
+    monthly_usd: Option<String>,
+}
+
+/// Token and spending limits for each calendar period
 #[derive(Debug, Serialize, Deserialize, Default)]
-struct BudgetConfig {
+struct Limits {
+    /// Input plus output tokens
     daily_limit: Option<u64>,
     weekly_limit: Option<u64>,
     monthly_limit: Option<u64>,
+    /// Estimated cost, cache reads and writes included
+    daily_usd: Option<f64>,
+    weekly_usd: Option<f64>,
+    monthly_usd: Option<f64>,
+}
+
+impl Limits {
+    /// Token and USD limits of each period
+    fn periods(&self) -> [(Period, Option<u64>, Option<f64>); 3] {
+        [
+            (Period::Day, self.daily_limit, self.daily_usd),
+            (Period::Week, self.weekly_limit, self.weekly_usd),
+            (Period::Month, self.monthly_limit, self.monthly_usd),
+        ]
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct SessionMessage {
-    message: Option<MessageContent>,
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct BudgetConfig {
+    /// Limits on all of the profile's usage
+    #[serde(flatten)]
+    limits: Limits,
+    /// Limits on usage in project directories, by directory or glob
+    #[serde(default)]
+    project_limits: BTreeMap<String, Limits>,
+    /// What the budget hook does once a limit is passed
+    #[serde(default)]
+    enforce: Enforcement,
+    /// Check before tool calls as well as prompts
+    #[serde(default)]
+    enforce_tools: bool,
+    /// Percentage of a limit from which the hook warns
+    warn_at_percent: Option<u8>,
+    /// Projects enforced differently from the profile, by directory or glob
+    #[serde(default)]
+    projects: BTreeMap<String, Enforcement>,
+    /// Enforcement is paused until then
+    bypass_until: Option<DateTime<Utc>>,
+}
+
+impl BudgetConfig {
+    /// Mode for an agent working in `cwd`: the most specific project
+    /// override, else the profile's own
+    fn enforcement_for(&self, cwd: &Path) -> Enforcement {
+        self.projects
+            .iter()
+            .filter(|(pattern, _)| in_project(pattern, cwd))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, mode)| *mode)
+            .unwrap_or(self.enforce)
+    }
+
+    /// Whether any agent would be checked, so the hook is needed
+    fn enforces(&self) -> bool {
+        self.enforce != Enforcement::Off || self.projects.values().any(|m| *m != Enforcement::Off)
+    }
+
+    fn bypassed(&self) -> bool {
+        self.bypass_until.map(|until| until > Utc::now()).unwrap_or(false)
+            || std::env::var_os(BYPASS_ENV).is_some()
+    }
+}
+
+/// Set in an agent's environment to let it past every limit
+const BYPASS_ENV: &str = "CODER_TOOLS_BUDGET_BYPASS";
+
+/// How the budget hook treats limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    #[default]
+    Off,
+    Warn,
+    Block,
+}
+
+impl Enforcement {
+    fn name(self) -> &'static str {
+        match self {
+            Enforcement::Off => "off",
+            Enforcement::Warn => "warn",
+            Enforcement::Block => "block",
+        }
+    }
+}
+
+/// Usage against one limit over its period
+struct LimitUse {
+    period: Period,
+    /// Project pattern, for a project's limit
+    project: Option<String>,
+    /// A spending limit rather than a token limit
+    usd: bool,
+    /// "1.5k/100k tokens" or "$1.20/$5.00"
+    amounts: String,
+    used: f64,
+    limit: f64,
+}
+
+impl LimitUse {
+    fn percent(&self) -> f64 {
+        if self.limit > 0.0 { self.used / self.limit * 100.0 } else { 100.0 }
+    }
+
+    fn exceeded(&self) -> bool {
+        self.used > self.limit
+    }
+
+    /// "daily limit (1.5k/100k tokens)", naming the project if it has one
+    fn describe(&self) -> String {
+        match self.project {
+            Some(ref project) => format!(
+                "{} limit for {} ({})",
+                self.period.name().to_lowercase(),
+                project,
+                self.amounts
+            ),
+            None => format!("{} limit ({})", self.period.name().to_lowercase(), self.amounts),
+        }
+    }
+}
+
+/// A limit's usage in its current period, for the monitor header
+#[derive(Debug, Clone)]
+pub struct Gauge {
+    /// "Daily", or "work daily" with several profiles
+    pub label: String,
+    /// "1.5k/100k tokens" or "$1.20/$5.00"
+    pub amounts: String,
+    pub percent: f64,
+    /// The limit and the period it is in, e.g. "default:Daily:tokens:2026-10-12"
+    key: String,
+}
+
+/// A threshold a limit has just crossed
+#[derive(Debug)]
+pub struct Alert {
+    pub title: String,
+    pub message: String,
+    /// The limit itself was reached
+    pub exceeded: bool,
+}
+
+/// What `report` groups usage by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Grouping {
+    Day,
+    Hour,
+    Weekday,
+    Project,
+    Profile,
+    Provider,
+    Session,
+    Model,
+}
+
+impl Grouping {
+    fn title(self) -> &'static str {
+        match self {
+            Grouping::Day => "Day",
+            Grouping::Hour => "Hour",
+            Grouping::Weekday => "Weekday",
+            Grouping::Project => "Project",
+            Grouping::Profile => "Profile",
+            Grouping::Provider => "Provider",
+            Grouping::Session => "Session",
+            Grouping::Model => "Model",
+        }
+    }
+
+    /// Listed in time order rather than by usage
+    fn chronological(self) -> bool {
+        matches!(self, Grouping::Day | Grouping::Hour | Grouping::Weekday)
+    }
+
+    /// The group a usage record falls in, written at local `time`
+    fn key(self, transcript: &Transcript, entry: &UsageEntry, time: DateTime<Local>) -> String {
+        match self {
+            Grouping::Day => time.format("%Y-%m-%d").to_string(),
+            Grouping::Hour => time.format("%H:00").to_string(),
+            Grouping::Weekday => time.format("%a").to_string(),
+            Grouping::Project => transcript.project_name.to_string(),
+            Grouping::Profile => transcript.profile.to_string(),
+            Grouping::Provider => transcript.provider.to_string(),
+            // Codex sessions are keyed by rollout path
+            Grouping::Session => Path::new(&transcript.session)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| transcript.session.clone()),
+            Grouping::Model => cost::short_model_name(&entry.model),
+        }
+    }
+
+    /// Order of two keys of a chronological grouping
+    fn cmp_keys(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Grouping::Weekday => {
+                let index = |day: &str| day.parse::<chrono::Weekday>().map(|d| d.num_days_from_monday()).ok();
+                index(a).cmp(&index(b))
+            }
+            _ => a.cmp(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+    Markdown,
+}
+
+/// Usage of one report group
+#[derive(Debug, Default)]
+struct GroupUsage {
+    input: u64,
+    output: u64,
+    /// Cache reads and writes
+    cache: u64,
+    cost: f64,
 }
 
-#[derive(Debug, Deserialize)]
-struct MessageContent {
-    usage: Option<TokenUsage>,
+impl GroupUsage {
+    fn add(&mut self, model: &str, usage: &ModelUsage) {
+        self.input += usage.input_tokens;
+        self.output += usage.output_tokens;
+        self.cache += usage.cache_read_tokens + usage.cache_write_tokens();
+        self.cost += usage.cost_usd(model);
+    }
+
+    fn total(&self) -> u64 {
+        self.input + self.output
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct TokenUsage {
-    input_tokens: Option<u64>,
-    output_tokens: Option<u64>,
+/// What the budget hook tells the agent
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Allow,
+    Warn(String),
+    Block(String),
 }
 
 #[derive(Debug, Default)]
 struct UsageStats {
     total_input: u64,
     total_output: u64,
-    session_count: u32,
-    by_day: std::collections::HashMap<String, DayUsage>,
-    by_project: std::collections::HashMap<String, u64>,
+    /// Part of the totals that subagents used on behalf of their sessions
+    subagent_input: u64,
+    subagent_output: u64,
+    /// Sessions with usage in the window, subagents counted under their parent
+    sessions: HashSet<String>,
+    /// Message and request ids already counted
+    seen: HashSet<String>,
+    by_profile: HashMap<String, u64>,
+    by_model: BTreeMap<String, ModelUsage>,
+    /// Usage per working directory and model, for project limits
+    by_dir: HashMap<String, BTreeMap<String, ModelUsage>>,
+    /// What `by_group` is keyed by, one key per grouping
+    grouping: Vec<Grouping>,
+    by_group: BTreeMap<Vec<String>, GroupUsage>,
+    /// Usage since each of these times as well, collected in the same pass
+    /// (totals, models and directories only)
+    since: Vec<(DateTime<Utc>, UsageStats)>,
 }
 
-#[derive(Debug, Default, Clone)]
-struct DayUsage {
-    input: u64,
-    output: u64,
+impl UsageStats {
+    fn total_tokens(&self) -> u64 {
+        self.total_input + self.total_output
+    }
+
+    fn cache_read_tokens(&self) -> u64 {
+        self.by_model.values().map(|u| u.cache_read_tokens).sum()
+    }
+
+    fn cache_write_tokens(&self) -> u64 {
+        self.by_model.values().map(|u| u.cache_write_tokens()).sum()
+    }
+
+    /// Estimated cost, each model at its own rates for input, output and cache
+    fn cost_usd(&self) -> f64 {
+        self.by_model.iter().map(|(model, usage)| usage.cost_usd(model)).sum()
+    }
+
+    /// Tokens and estimated cost, of all usage or of a project's only
+    fn totals(&self, project: Option<&str>) -> (u64, f64) {
+        let pattern = match project {
+            Some(pattern) => pattern,
+            None => return (self.total_tokens(), self.cost_usd()),
+        };
+        self.by_dir
+            .iter()
+            .filter(|(dir, _)| in_project(pattern, Path::new(dir)))
+            .flat_map(|(_, models)| models)
+            .fold((0, 0.0), |(tokens, cost), (model, usage)| {
+                (tokens + usage.input_tokens + usage.output_tokens, cost + usage.cost_usd(model))
+            })
+    }
 }
 
-pub fn run(action: BudgetAction) -> Result<()> {
-    match action {
-        BudgetAction::Status => show_status(),
-        BudgetAction::Set { daily, weekly, monthly } => set_limits(daily, weekly, monthly),
-        BudgetAction::Report { days, group_by } => show_report(days, &group_by),
-        BudgetAction::Reset { confirm } => reset_usage(confirm),
+/// Whether `dir` belongs to a project: the pattern (a directory or glob)
+/// matches it or one of its parents
+fn in_project(pattern: &str, dir: &Path) -> bool {
+    let pattern = match glob::Pattern::new(&config::expand_home(pattern).to_string_lossy()) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    dir.ancestors().any(|d| pattern.matches_path_with(d, options))
+}
+
+/// A project pattern as given to `set --project`, checked to be absolute
+fn project_pattern(pattern: &str) -> Result<String> {
+    let pattern = pattern.trim_end_matches('/');
+    let expanded = config::expand_home(pattern);
+    if !expanded.is_absolute() {
+        anyhow::bail!("Project must be an absolute path or start with ~/: {}", pattern);
+    }
+    glob::Pattern::new(&expanded.to_string_lossy()).with_context(|| format!("Invalid pattern: {}", pattern))?;
+    Ok(pattern.to_string())
+}
+
+/// A profile's usage in each period, all read in one pass when first needed
+struct PeriodUsage<'a> {
+    profile: &'a Profile,
+    /// By period, in `Period::ALL` order
+    stats: Vec<UsageStats>,
+}
+
+impl<'a> PeriodUsage<'a> {
+    fn new(profile: &'a Profile) -> Self {
+        PeriodUsage { profile, stats: Vec::new() }
+    }
+
+    fn get(&mut self, period: Period) -> Result<&UsageStats> {
+        if self.stats.is_empty() {
+            self.stats = calculate_usage_since(&Period::ALL.map(Period::start), &[self.profile])?;
+        }
+        Ok(&self.stats[period as usize])
     }
 }
 
-fn get_config_path() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".claude").join("budget.json"))
+pub fn run(action: BudgetAction) -> Result<()> {
+    // The budget hook runs on every prompt, often several at once, so it only
+    // writes the index back once it has fallen well behind
+    let min_read = match action {
+        BudgetAction::Check { .. } => usage_index::SAVE_AFTER_BYTES,
+        _ => 0,
+    };
+    let result = match action {
+        BudgetAction::Status => show_status(),
+        BudgetAction::Set(limits) => set_limits(limits),
+        BudgetAction::Report { days, group_by, format } => show_report(days, &group_by, format),
+        BudgetAction::Enforce { mode, project, tools, warn_at, yes } => {
+            set_enforcement(mode, project, tools, warn_at, yes)
+        }
+        BudgetAction::Bypass { minutes, clear } => set_bypass(minutes, clear),
+        BudgetAction::Check { event } => check(&event),
+        BudgetAction::Reset { confirm } => reset_usage(confirm),
+    };
+    // The index is only a cache; failing to write it just makes the next run slower
+    let _ = usage_index::save_if_behind(min_read);
+    result
 }
 
-fn get_claude_projects_dir() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".claude").join("projects"))
+/// Limits are kept per profile, next to its transcripts
+fn get_config_path(profile: &Profile) -> PathBuf {
+    profile.dir.join("budget.json")
 }
 
-fn load_config() -> Result<BudgetConfig> {
-    let path = get_config_path()?;
+fn load_config(profile: &Profile) -> Result<BudgetConfig> {
+    let path = get_config_path(profile);
     if path.exists() {
         let content = fs::read_to_string(&path)?;
         Ok(serde_json::from_str(&content)?)
@@ -110,8 +513,8 @@ fn load_config() -> Result<BudgetConfig> {
     }
 }
 
-fn save_config(config: &BudgetConfig) -> Result<()> {
-    let path = get_config_path()?;
+fn save_config(profile: &Profile, config: &BudgetConfig) -> Result<()> {
+    let path = get_config_path(profile);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
@@ -134,6 +537,15 @@ fn parse_token_limit(s: &str) -> Result<u64> {
     }
 }
 
+/// Dollar amount, with or without a leading $
+fn parse_usd_limit(s: &str) -> Result<f64> {
+    let amount: f64 = s.trim().trim_start_matches('$').parse()?;
+    if !amount.is_finite() || amount < 0.0 {
+        anyhow::bail!("Invalid amount: {}", s);
+    }
+    Ok(amount)
+}
+
 fn format_tokens(n: u64) -> String {
     if n >= 1_000_000 {
         format!("{:.1}M", n as f64 / 1_000_000.0)
@@ -144,17 +556,200 @@ fn format_tokens(n: u64) -> String {
     }
 }
 
-fn calculate_usage(days: u32) -> Result<UsageStats> {
-    let projects_dir = get_claude_projects_dir()?;
+/// Calendar periods that budget limits apply to, in local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    const ALL: [Period; 3] = [Period::Day, Period::Week, Period::Month];
+
+    /// First day of the period that contains `today`; weeks start on Monday
+    fn first_day(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => today,
+            Period::Week => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            Period::Month => today.with_day(1).unwrap_or(today),
+        }
+    }
+
+    /// When the current period began
+    fn start(self) -> DateTime<Local> {
+        local_midnight(self.first_day(Local::now().date_naive()))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Period::Day => "Daily",
+            Period::Week => "Weekly",
+            Period::Month => "Monthly",
+        }
+    }
+}
+
+/// Start of the last `days` calendar days, today included
+fn days_start(days: u32) -> DateTime<Local> {
+    let today = Local::now().date_naive();
+    local_midnight(today - Duration::days(days.saturating_sub(1) as i64))
+}
+
+fn local_midnight(day: NaiveDate) -> DateTime<Local> {
+    let midnight = day.and_time(NaiveTime::MIN);
+    // Where a DST change skips midnight, the day starts an hour later
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .unwrap_or_else(Local::now)
+}
+
+/// Usage since `since` of the given profiles; Codex belongs to no profile
+/// and only counts when all of them are included
+fn calculate_usage(since: DateTime<Local>, profiles: &[&Profile]) -> Result<UsageStats> {
     let mut stats = UsageStats::default();
+    collect_usage(&mut stats, since, profiles)?;
+    Ok(stats)
+}
 
-    if !projects_dir.exists() {
-        return Ok(stats);
+/// Usage since each of `starts`, read in one pass from the earliest
+fn calculate_usage_since(starts: &[DateTime<Local>], profiles: &[&Profile]) -> Result<Vec<UsageStats>> {
+    let earliest = match starts.iter().min() {
+        Some(earliest) => *earliest,
+        None => return Ok(Vec::new()),
+    };
+    let mut stats = UsageStats {
+        since: starts.iter().map(|start| (start.with_timezone(&Utc), UsageStats::default())).collect(),
+        ..Default::default()
+    };
+    collect_usage(&mut stats, earliest, profiles)?;
+    Ok(stats.since.into_iter().map(|(_, stats)| stats).collect())
+}
+
+/// Add usage since `since` to `stats`, grouped by its `grouping`
+fn collect_usage(stats: &mut UsageStats, since: DateTime<Local>, profiles: &[&Profile]) -> Result<()> {
+    let since = since.with_timezone(&Utc);
+
+    usage_index::with_index(|index| {
+        for profile in profiles {
+            add_claude_usage(stats, index, profile, since)?;
+        }
+        if profile::covers_all(profiles) {
+            add_codex_usage(stats, index, since);
+        }
+        Ok(())
+    })
+}
+
+/// Where a transcript's records are accounted
+struct Transcript<'a> {
+    provider: &'static str,
+    profile: &'a str,
+    project_name: &'a str,
+    /// Session the usage belongs to (a subagent's parent)
+    session: String,
+    /// Directory the agent worked in, for lines that don't record their own
+    cwd: Option<String>,
+    /// Written by a subagent, so all of it is subagent usage
+    subagent: bool,
+    /// For lines without a timestamp
+    modified: SystemTime,
+}
+
+/// Add one usage record to the totals and the profile/model/directory/report groupings
+fn add_usage(stats: &mut UsageStats, transcript: &Transcript, entry: &UsageEntry, time: DateTime<Utc>) {
+    let input = entry.usage.input_tokens;
+    let output = entry.usage.output_tokens;
+
+    stats.total_input += input;
+    stats.total_output += output;
+    if transcript.subagent || entry.sidechain {
+        stats.subagent_input += input;
+        stats.subagent_output += output;
     }
 
-    let cutoff = std::time::SystemTime::now()
-        .checked_sub(std::time::Duration::from_secs(days as u64 * 86400))
-        .unwrap_or(std::time::UNIX_EPOCH);
+    *stats.by_profile.entry(transcript.profile.to_string()).or_insert(0) += input + output;
+    stats.by_model.entry(entry.model.clone()).or_default().add(&entry.usage);
+    // A session that changes directory is accounted where each line was written
+    if let Some(cwd) = entry.cwd.as_ref().or(transcript.cwd.as_ref()) {
+        let models = stats.by_dir.entry(cwd.clone()).or_default();
+        models.entry(entry.model.clone()).or_default().add(&entry.usage);
+    }
+
+    if !stats.grouping.is_empty() {
+        let local = time.with_timezone(&Local);
+        let key = stats.grouping.iter().map(|g| g.key(transcript, entry, local)).collect();
+        stats.by_group.entry(key).or_default().add(&entry.model, &entry.usage);
+    }
+
+    for (start, since) in stats.since.iter_mut() {
+        if time >= *start {
+            add_usage(since, transcript, entry, time);
+        }
+    }
+}
+
+/// Add a transcript's records written since `since`
+///
+/// Lines are placed by their own timestamp; the few without one fall back
+/// to the file's modification time. A message repeated across streamed
+/// lines or copied into a resumed session is counted once.
+fn add_transcript(stats: &mut UsageStats, transcript: Transcript, entries: &[UsageEntry], since: DateTime<Utc>) {
+    let modified = DateTime::<Utc>::from(transcript.modified);
+    let mut counted = false;
+    for entry in entries {
+        let time = entry.timestamp.unwrap_or(modified);
+        if time < since {
+            continue;
+        }
+        if let Some(ref id) = entry.id {
+            if !stats.seen.insert(id.clone()) {
+                continue;
+            }
+        }
+        add_usage(stats, &transcript, entry, time);
+        counted = true;
+    }
+    if counted {
+        stats.sessions.insert(transcript.session);
+    }
+}
+
+/// Session transcripts of a Claude project, and their subagents' under
+/// `<session>/subagents/`
+fn claude_transcripts(project_dir: &Path) -> Vec<PathBuf> {
+    let jsonl_files = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
+            .collect()
+    };
+
+    let mut files = jsonl_files(project_dir);
+    for entry in fs::read_dir(project_dir).into_iter().flatten().filter_map(|e| e.ok()) {
+        if entry.path().is_dir() {
+            files.extend(jsonl_files(&entry.path().join("subagents")));
+        }
+    }
+    files
+}
+
+fn add_claude_usage(
+    stats: &mut UsageStats,
+    index: &mut UsageIndex,
+    profile: &Profile,
+    since: DateTime<Utc>,
+) -> Result<()> {
+    let projects_dir = profile.projects_dir();
+
+    if !projects_dir.exists() {
+        return Ok(());
+    }
 
     for entry in fs::read_dir(&projects_dir)? {
         let entry = entry?;
@@ -169,61 +764,70 @@ fn calculate_usage(days: u32) -> Result<UsageStats> {
             .unwrap_or("unknown")
             .to_string();
 
-        for file_entry in fs::read_dir(&path)? {
-            let file_entry = file_entry?;
-            let file_path = file_entry.path();
-
-            if !file_path.extension().map(|e| e == "jsonl").unwrap_or(false) {
+        for file_path in claude_transcripts(&path) {
+            // Gone since the listing (deleted or rotated): nothing to count
+            let Ok(modified) = fs::metadata(&file_path).and_then(|m| m.modified()) else {
                 continue;
-            }
-
-            let metadata = fs::metadata(&file_path)?;
-            if metadata.modified()? < cutoff {
+            };
+            // A file last written before the window has nothing in it
+            if DateTime::<Utc>::from(modified) < since {
                 continue;
             }
 
-            stats.session_count += 1;
-
-            if let Ok(content) = fs::read_to_string(&file_path) {
-                for line in content.lines() {
-                    if let Ok(msg) = serde_json::from_str::<SessionMessage>(line) {
-                        if let Some(message) = msg.message {
-                            if let Some(usage) = message.usage {
-                                let input = usage.input_tokens.unwrap_or(0);
-                                let output = usage.output_tokens.unwrap_or(0);
-
-                                stats.total_input += input;
-                                stats.total_output += output;
-
-                                *stats.by_project.entry(project_name.clone()).or_insert(0) += input + output;
-
-                                // Group by day (using file modification time as proxy)
-                                let day = get_day_string(metadata.modified()?);
-                                let day_usage = stats.by_day.entry(day).or_insert_with(DayUsage::default);
-                                day_usage.input += input;
-                                day_usage.output += output;
-                            }
-                        }
-                    }
-                }
-            }
+            let entries = index.entries(&file_path, IndexKind::Claude);
+            // Lines name their session; a subagent's lines name its parent
+            let session = entries
+                .iter()
+                .find_map(|e| e.session_id.clone())
+                .or_else(|| file_path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                .unwrap_or_default();
+            let transcript = Transcript {
+                provider: "claude",
+                profile: &profile.name,
+                project_name: &project_name,
+                session,
+                cwd: entries.iter().find_map(|e| e.cwd.clone()),
+                subagent: claude::is_subagent_transcript(&file_path),
+                modified,
+            };
+            add_transcript(stats, transcript, entries, since);
         }
     }
 
-    Ok(stats)
+    Ok(())
 }
 
-fn get_day_string(time: std::time::SystemTime) -> String {
-    let duration = time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
-    let days = duration.as_secs() / 86400;
-    format!("day-{}", days)
+/// Codex rollouts, grouped under the same project names as Claude
+fn add_codex_usage(stats: &mut UsageStats, index: &mut UsageIndex, since: DateTime<Utc>) {
+    for (path, modified) in codex::rollout_files() {
+        if DateTime::<Utc>::from(modified) < since {
+            // Newest first, so everything after this is older too
+            break;
+        }
+
+        let cwd = codex::read_session_meta(&path).map(|meta| meta.cwd);
+        let project_name = cwd
+            .as_deref()
+            .map(claude::hash_path)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let entries = index.entries(&path, IndexKind::Codex);
+        let transcript = Transcript {
+            provider: "codex",
+            profile: "codex",
+            project_name: &project_name,
+            session: path.to_string_lossy().into_owned(),
+            cwd,
+            subagent: false,
+            modified,
+        };
+        add_transcript(stats, transcript, entries, since);
+    }
 }
 
 fn show_status() -> Result<()> {
-    let config = load_config()?;
-    let stats = calculate_usage(30)?;
-
-    let total = stats.total_input + stats.total_output;
+    let profiles = profile::active();
+    let stats = calculate_usage(days_start(30), &profiles)?;
 
     println!("Token Usage Status");
     println!("{}", "=".repeat(50));
@@ -232,132 +836,823 @@ fn show_status() -> Result<()> {
     println!("Current Usage (last 30 days):");
     println!("  Input tokens:  {}", format_tokens(stats.total_input));
     println!("  Output tokens: {}", format_tokens(stats.total_output));
-    println!("  Total:         {}", format_tokens(total));
-    println!("  Sessions:      {}", stats.session_count);
+    println!("  Total:         {}", format_tokens(stats.total_tokens()));
+    println!("  Cache read:    {}", format_tokens(stats.cache_read_tokens()));
+    println!("  Cache write:   {}", format_tokens(stats.cache_write_tokens()));
+    if stats.subagent_input + stats.subagent_output > 0 {
+        println!(
+            "  Subagents:     {} (included above)",
+            format_tokens(stats.subagent_input + stats.subagent_output)
+        );
+    }
+    println!("  Sessions:      {}", stats.sessions.len());
+    println!("  Cost:          {}", cost::format_cost(stats.cost_usd()));
     println!();
 
+    if profile::has_several() {
+        print_profile_breakdown(&stats);
+    }
+    print_model_breakdown(&stats);
+
     println!("Budget Limits:");
 
-    if let Some(daily) = config.daily_limit {
-        let daily_usage = calculate_usage(1)?.total_input + calculate_usage(1)?.total_output;
-        let pct = (daily_usage as f64 / daily as f64 * 100.0).min(100.0);
-        let status = if daily_usage > daily { "\x1b[31mEXCEEDED\x1b[0m" } else { "\x1b[32mOK\x1b[0m" };
-        println!("  Daily:   {}/{} ({:.0}%) {}", format_tokens(daily_usage), format_tokens(daily), pct, status);
-    } else {
-        println!("  Daily:   not set");
+    for profile in &profiles {
+        // Each profile has its own limits, against its own usage
+        let indent = if profile::has_several() {
+            println!("  {} ({}):", profile.name, profile.dir.display());
+            "    "
+        } else {
+            "  "
+        };
+        let config = load_config(profile)?;
+        let uses = all_limit_uses(profile, &config, None)?;
+
+        // Limits cover calendar periods: today, this week, this month
+        print_limits(&uses, None, indent, true);
+        for pattern in config.project_limits.keys() {
+            println!("{}{}:", indent, pattern);
+            print_limits(&uses, Some(pattern), &format!("{}  ", indent), false);
+        }
+        print_enforcement(&config, indent);
     }
 
-    if let Some(weekly) = config.weekly_limit {
-        let weekly_usage = calculate_usage(7)?.total_input + calculate_usage(7)?.total_output;
-        let pct = (weekly_usage as f64 / weekly as f64 * 100.0).min(100.0);
-        let status = if weekly_usage > weekly { "\x1b[31mEXCEEDED\x1b[0m" } else { "\x1b[32mOK\x1b[0m" };
-        println!("  Weekly:  {}/{} ({:.0}%) {}", format_tokens(weekly_usage), format_tokens(weekly), pct, status);
-    } else {
-        println!("  Weekly:  not set");
+    println!();
+    println!("Use 'claude-tools budget set --daily 100k' or '--daily-usd 5' to set limits");
+    println!("Use 'claude-tools budget report' for detailed breakdown");
+
+    Ok(())
+}
+
+/// One line per limit of the profile or of a project, each period labelled once
+fn print_limits(uses: &[LimitUse], project: Option<&str>, indent: &str, show_unset: bool) {
+    for period in Period::ALL {
+        let label = format!("{}:", period.name());
+        let lines: Vec<_> = uses
+            .iter()
+            .filter(|u| u.period == period && u.project.as_deref() == project)
+            .map(limit_line)
+            .collect();
+        if lines.is_empty() && show_unset {
+            println!("{}{:<8} not set", indent, label);
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let label = if i == 0 { label.as_str() } else { "" };
+            println!("{}{:<8} {}", indent, label, line);
+        }
     }
+}
 
-    if let Some(monthly) = config.monthly_limit {
-        let pct = (total as f64 / monthly as f64 * 100.0).min(100.0);
-        let status = if total > monthly { "\x1b[31mEXCEEDED\x1b[0m" } else { "\x1b[32mOK\x1b[0m" };
-        println!("  Monthly: {}/{} ({:.0}%) {}", format_tokens(total), format_tokens(monthly), pct, status);
-    } else {
-        println!("  Monthly: not set");
+/// Usage against the profile's limits and its projects', or only the
+/// projects that contain `cwd` when given
+fn all_limit_uses(profile: &Profile, config: &BudgetConfig, cwd: Option<&Path>) -> Result<Vec<LimitUse>> {
+    let mut usage = PeriodUsage::new(profile);
+    let mut uses = limit_uses(&mut usage, &config.limits, None)?;
+    for (pattern, limits) in &config.project_limits {
+        if cwd.map(|cwd| in_project(pattern, cwd)).unwrap_or(true) {
+            uses.extend(limit_uses(&mut usage, limits, Some(pattern))?);
+        }
     }
+    Ok(uses)
+}
 
-    println!();
-    println!("Use 'claude-tools budget set --daily 100k' to set limits");
-    println!("Use 'claude-tools budget report' for detailed breakdown");
+/// Usage against each limit that is set, over the limit's own period
+fn limit_uses(usage: &mut PeriodUsage, limits: &Limits, project: Option<&str>) -> Result<Vec<LimitUse>> {
+    let mut uses = Vec::new();
+    for (period, tokens, usd) in limits.periods() {
+        if tokens.is_none() && usd.is_none() {
+            continue;
+        }
+        let (used_tokens, used_usd) = usage.get(period)?.totals(project);
+        if let Some(limit) = tokens {
+            let used = used_tokens;
+            uses.push(LimitUse {
+                period,
+                project: project.map(str::to_string),
+                usd: false,
+                amounts: format!("{}/{} tokens", format_tokens(used), format_tokens(limit)),
+                used: used as f64,
+                limit: limit as f64,
+            });
+        }
+        if let Some(limit) = usd {
+            let used = used_usd;
+            uses.push(LimitUse {
+                period,
+                project: project.map(str::to_string),
+                usd: true,
+                amounts: format!("{}/{}", cost::format_cost(used), cost::format_cost(limit)),
+                used,
+                limit,
+            });
+        }
+    }
+    Ok(uses)
+}
+
+/// "1.5k/100k tokens (2%) OK", red once the limit is passed
+fn limit_line(usage: &LimitUse) -> String {
+    let status = if usage.exceeded() { "\x1b[31mEXCEEDED\x1b[0m" } else { "\x1b[32mOK\x1b[0m" };
+    format!("{} ({:.0}%) {}", usage.amounts, usage.percent().min(100.0), status)
+}
+
+/// Enforcement mode, overrides and any bypass, under a profile's limits
+fn print_enforcement(config: &BudgetConfig, indent: &str) {
+    let mut mode = config.enforce.name().to_string();
+    if config.enforce_tools && config.enforces() {
+        mode.push_str(", tool calls included");
+    }
+    if let Some(pct) = config.warn_at_percent {
+        mode.push_str(&format!(", warns from {}%", pct));
+    }
+    println!("{}{:<8} {}", indent, "Enforce:", mode);
+    for (pattern, mode) in &config.projects {
+        println!("{}{:<8}   {} in {}", indent, "", mode.name(), pattern);
+    }
+    if let Some(until) = config.bypass_until.filter(|until| *until > Utc::now()) {
+        println!("{}{:<8}   bypassed until {}", indent, "", until.with_timezone(&Local).format("%Y-%m-%d %H:%M"));
+    }
+}
+
+/// Usage against every limit of the active profiles
+pub fn gauges() -> Result<Vec<Gauge>> {
+    let today = Local::now().date_naive();
+    let mut gauges = Vec::new();
+    for profile in profile::active() {
+        let config = load_config(profile)?;
+        for usage in all_limit_uses(profile, &config, None)? {
+            let mut label = match usage.project {
+                Some(ref project) => format!("{} {}", project, usage.period.name().to_lowercase()),
+                None => usage.period.name().to_string(),
+            };
+            if profile::has_several() {
+                label = format!("{} {}", profile.name, label.to_lowercase());
+            }
+            let key = format!(
+                "{}:{}:{}:{}:{}",
+                profile.name,
+                usage.project.as_deref().unwrap_or(""),
+                usage.period.name(),
+                if usage.usd { "usd" } else { "tokens" },
+                usage.period.first_day(today)
+            );
+            gauges.push(Gauge {
+                label,
+                percent: usage.percent(),
+                amounts: usage.amounts,
+                key,
+            });
+        }
+    }
+    Ok(gauges)
+}
+
+/// Where crossed thresholds are remembered, so a restarted monitor
+/// doesn't alert again
+fn alerts_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|d| d.join("coder-tools").join("budget-alerts.json"))
+}
+
+/// Alerts for thresholds crossed since last time, at most once per
+/// threshold, limit and period
+pub fn new_alerts(gauges: &[Gauge], thresholds: &[u8]) -> Result<Vec<Alert>> {
+    let path = alerts_path().context("Could not determine state directory")?;
+    let mut fired: BTreeMap<String, u8> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    let before = fired.clone();
+
+    let alerts = update_alerts(&mut fired, gauges, thresholds);
+    if fired != before {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&fired)?)?;
+    }
+    Ok(alerts)
+}
+
+/// Alert on the highest threshold each gauge has newly passed, recording it
+/// in `fired`; periods that have ended are forgotten
+fn update_alerts(fired: &mut BTreeMap<String, u8>, gauges: &[Gauge], thresholds: &[u8]) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for gauge in gauges {
+        let previous = fired.get(&gauge.key).copied().unwrap_or(0);
+        let crossed = thresholds
+            .iter()
+            .copied()
+            .filter(|t| gauge.percent >= *t as f64 && *t > previous)
+            .max();
+        if let Some(threshold) = crossed {
+            alerts.push(Alert {
+                title: format!("Budget: {}% of {} limit", threshold, gauge.label.to_lowercase()),
+                message: format!("{} used", gauge.amounts),
+                exceeded: threshold >= 100,
+            });
+            fired.insert(gauge.key.clone(), threshold);
+        }
+    }
+    fired.retain(|key, _| gauges.iter().any(|g| &g.key == key));
+    alerts
+}
+
+fn set_limits(args: LimitArgs) -> Result<()> {
+    let profile = profile::single()?;
+    let mut config = load_config(profile)?;
+
+    let project = args.project.as_deref().map(project_pattern).transpose()?;
+    let (limits, scope) = match project {
+        Some(pattern) if args.remove => {
+            if config.project_limits.remove(&pattern).is_none() {
+                anyhow::bail!("No limits set for {}", pattern);
+            }
+            println!("Removed the limits for {}", pattern);
+            save_config(profile, &config)?;
+            return Ok(());
+        }
+        Some(pattern) => {
+            let given = [&args.daily, &args.weekly, &args.monthly, &args.daily_usd, &args.weekly_usd, &args.monthly_usd];
+            if given.iter().all(|value| value.is_none()) {
+                anyhow::bail!("No limits given for {}", pattern);
+            }
+            let scope = format!(" for {}", pattern);
+            (config.project_limits.entry(pattern).or_default(), scope)
+        }
+        None => (&mut config.limits, String::new()),
+    };
+
+    for (label, value, slot) in [
+        ("Daily", args.daily, &mut limits.daily_limit),
+        ("Weekly", args.weekly, &mut limits.weekly_limit),
+        ("Monthly", args.monthly, &mut limits.monthly_limit),
+    ] {
+        if let Some(value) = value {
+            let limit = parse_token_limit(&value)?;
+            *slot = Some(limit);
+            println!("{} limit{} set to: {}", label, scope, format_tokens(limit));
+        }
+    }
+
+    for (label, value, slot) in [
+        ("Daily", args.daily_usd, &mut limits.daily_usd),
+        ("Weekly", args.weekly_usd, &mut limits.weekly_usd),
+        ("Monthly", args.monthly_usd, &mut limits.monthly_usd),
+    ] {
+        if let Some(value) = value {
+            let limit = parse_usd_limit(&value)?;
+            *slot = Some(limit);
+            println!("{} spending limit{} set to: {}", label, scope, cost::format_cost(limit));
+        }
+    }
+
+    save_config(profile, &config)?;
+    println!("\nConfig saved to: {}", get_config_path(profile).display());
 
     Ok(())
 }
 
-fn set_limits(daily: Option<String>, weekly: Option<String>, monthly: Option<String>) -> Result<()> {
-    let mut config = load_config()?;
+fn set_enforcement(mode: Enforcement, project: Option<String>, tools: bool, warn_at: Option<u8>, yes: bool) -> Result<()> {
+    let profile = profile::single()?;
+    let mut config = load_config(profile)?;
 
-    if let Some(d) = daily {
-        config.daily_limit = Some(parse_token_limit(&d)?);
-        println!("Daily limit set to: {}", format_tokens(config.daily_limit.unwrap()));
+    match project.as_deref().map(project_pattern).transpose()? {
+        Some(pattern) => {
+            println!("Budget enforcement in {}: {}", pattern, mode.name());
+            config.projects.insert(pattern, mode);
+        }
+        None => {
+            config.enforce = mode;
+            config.enforce_tools = tools;
+            println!("Budget enforcement: {}", mode.name());
+        }
+    }
+    if warn_at.is_some() {
+        config.warn_at_percent = warn_at;
     }
 
-    if let Some(w) = weekly {
-        config.weekly_limit = Some(parse_token_limit(&w)?);
-        println!("Weekly limit set to: {}", format_tokens(config.weekly_limit.unwrap()));
+    let events: &[&'static str] = match (config.enforces(), config.enforce_tools) {
+        (false, _) => &[],
+        (true, false) => &["UserPromptSubmit"],
+        (true, true) => &["UserPromptSubmit", "PreToolUse"],
+    };
+    // Without the hook the new mode would have no effect, so keep the old one
+    if !hooks::wire_budget_hooks(&profile.settings_path(), events, yes)? {
+        return Ok(());
     }
 
-    if let Some(m) = monthly {
-        config.monthly_limit = Some(parse_token_limit(&m)?);
-        println!("Monthly limit set to: {}", format_tokens(config.monthly_limit.unwrap()));
+    save_config(profile, &config)?;
+    println!("\nConfig saved to: {}", get_config_path(profile).display());
+    Ok(())
+}
+
+fn set_bypass(minutes: u32, clear: bool) -> Result<()> {
+    let profile = profile::single()?;
+    let mut config = load_config(profile)?;
+
+    if clear {
+        config.bypass_until = None;
+        println!("Budget enforcement resumed.");
+    } else {
+        let until = Utc::now() + Duration::minutes(minutes as i64);
+        config.bypass_until = Some(until);
+        println!(
+            "Budget enforcement paused until {}.",
+            until.with_timezone(&Local).format("%H:%M")
+        );
     }
 
-    save_config(&config)?;
-    println!("\nConfig saved to: {}", get_config_path()?.display());
+    save_config(profile, &config)
+}
 
+/// Budget hook handler: warn or block according to the profile's limits
+///
+/// Like the status hook it must never get in the agent's way by failing,
+/// so any error lets the prompt through.
+fn check(event: &str) -> Result<()> {
+    let payload = hook::read_stdin()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(Value::Null);
+
+    if let Ok(Some(output)) = check_output(event, &payload) {
+        println!("{}", output);
+    }
     Ok(())
 }
 
-fn show_report(days: u32, group_by: &str) -> Result<()> {
-    let stats = calculate_usage(days)?;
+/// Hook output for an event, in Claude Code's hook protocol
+fn check_output(event: &str, payload: &Value) -> Result<Option<Value>> {
+    let profile = hook::claude_profile(payload).context("No Claude Code profile")?;
+    let config = load_config(profile)?;
+    let cwd = payload
+        .get("cwd")
+        .and_then(|c| c.as_str())
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+
+    let mode = config.enforcement_for(&cwd);
+    if mode == Enforcement::Off || config.bypassed() {
+        return Ok(None);
+    }
+
+    let uses = all_limit_uses(profile, &config, Some(&cwd))?;
+    Ok(match (verdict(mode, config.warn_at_percent, &uses), event) {
+        (Verdict::Block(reason), "PreToolUse") => Some(json!({
+            "hookSpecificOutput": {
+                "hookEventName": "PreToolUse",
+                "permissionDecision": "deny",
+                "permissionDecisionReason": reason,
+            }
+        })),
+        (Verdict::Block(reason), _) => Some(json!({ "decision": "block", "reason": reason })),
+        // Warning on every tool call would bury the conversation
+        (Verdict::Warn(message), "UserPromptSubmit") => Some(json!({ "systemMessage": message })),
+        _ => None,
+    })
+}
+
+/// Block or warn on limits passed, and warn on those past `warn_at` percent
+fn verdict(mode: Enforcement, warn_at: Option<u8>, uses: &[LimitUse]) -> Verdict {
+    if mode == Enforcement::Off {
+        return Verdict::Allow;
+    }
 
+    let exceeded: Vec<_> = uses.iter().filter(|u| u.exceeded()).map(LimitUse::describe).collect();
+    if !exceeded.is_empty() {
+        let message = format!("Budget exceeded: {}.", exceeded.join(", "));
+        if mode == Enforcement::Warn {
+            return Verdict::Warn(message);
+        }
+        return Verdict::Block(format!(
+            "{} Raise the limit with 'coder-tools budget set', or run 'coder-tools budget bypass' to carry on for an hour.",
+            message
+        ));
+    }
+
+    let near: Vec<_> = uses
+        .iter()
+        .filter(|u| warn_at.map(|pct| u.percent() >= pct as f64).unwrap_or(false))
+        .map(|u| format!("{:.0}% of the {}", u.percent(), u.describe()))
+        .collect();
+    if near.is_empty() {
+        Verdict::Allow
+    } else {
+        Verdict::Warn(format!("Budget nearly used: {}.", near.join(", ")))
+    }
+}
+
+fn show_report(days: u32, group_by: &[Grouping], format: ReportFormat) -> Result<()> {
+    if group_by.len() > 2 {
+        anyhow::bail!("Group by at most two of day, hour, weekday, project, profile, provider, session, model");
+    }
+    let mut stats = UsageStats {
+        grouping: group_by.to_vec(),
+        ..Default::default()
+    };
+    collect_usage(&mut stats, days_start(days), &profile::active())?;
+    let rows = report_rows(&stats);
+
+    match format {
+        ReportFormat::Table => print_report_table(days, &stats, &rows),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report_json(days, group_by, &rows))?),
+        ReportFormat::Csv => print!("{}", report_csv(group_by, &rows)),
+        ReportFormat::Markdown => print!("{}", report_markdown(days, group_by, &rows)),
+    }
+    Ok(())
+}
+
+/// Report groups in order: time groupings chronologically, others by
+/// tokens used, the first grouping by its overall total
+fn report_rows(stats: &UsageStats) -> Vec<(&[String], &GroupUsage)> {
+    let mut rows: Vec<(&[String], &GroupUsage)> =
+        stats.by_group.iter().map(|(keys, usage)| (keys.as_slice(), usage)).collect();
+
+    let mut first_totals: HashMap<&str, u64> = HashMap::new();
+    for (keys, usage) in &rows {
+        *first_totals.entry(keys[0].as_str()).or_default() += usage.total();
+    }
+
+    rows.sort_by(|(a, a_usage), (b, b_usage)| {
+        stats.grouping.iter().enumerate().fold(Ordering::Equal, |ordering, (i, grouping)| {
+            ordering.then_with(|| {
+                if grouping.chronological() {
+                    return grouping.cmp_keys(&a[i], &b[i]);
+                }
+                let (a_total, b_total) = if i == 0 {
+                    (first_totals[a[0].as_str()], first_totals[b[0].as_str()])
+                } else {
+                    (a_usage.total(), b_usage.total())
+                };
+                b_total.cmp(&a_total).then_with(|| a[i].cmp(&b[i]))
+            })
+        })
+    });
+    rows
+}
+
+/// Totals over all report rows
+fn report_total(rows: &[(&[String], &GroupUsage)]) -> GroupUsage {
+    let mut total = GroupUsage::default();
+    for (_, usage) in rows {
+        total.input += usage.input;
+        total.output += usage.output;
+        total.cache += usage.cache;
+        total.cost += usage.cost;
+    }
+    total
+}
+
+fn print_report_table(days: u32, stats: &UsageStats, rows: &[(&[String], &GroupUsage)]) {
     println!("Usage Report (last {} days)", days);
     println!("{}", "=".repeat(50));
     println!();
 
-    match group_by {
-        "project" => {
-            println!("{:<30} {}", "Project", "Tokens");
-            println!("{}", "-".repeat(50));
-
-            let mut projects: Vec<_> = stats.by_project.iter().collect();
-            projects.sort_by(|a, b| b.1.cmp(a.1));
+    // Key columns fit their longest value
+    let widths: Vec<usize> = stats
+        .grouping
+        .iter()
+        .enumerate()
+        .map(|(i, g)| rows.iter().map(|(keys, _)| keys[i].chars().count()).max().unwrap_or(0).max(g.title().len()) + 2)
+        .collect();
+
+    let mut header = String::new();
+    for (grouping, width) in stats.grouping.iter().zip(&widths) {
+        header.push_str(&format!("{:<width$}", grouping.title(), width = width));
+    }
+    println!("{}{:<10} {:<10} {:<10} {:<10} Cost", header, "Input", "Output", "Cache", "Total");
+    println!("{}", "-".repeat(header.len() + 50));
 
-            for (project, tokens) in projects {
-                println!("{:<30} {}", project, format_tokens(*tokens));
-            }
-        }
-        "day" => {
-            println!("{:<15} {:<15} {:<15} {}", "Day", "Input", "Output", "Total");
-            println!("{}", "-".repeat(60));
-
-            let mut days: Vec<_> = stats.by_day.iter().collect();
-            days.sort_by(|a, b| a.0.cmp(b.0));
-
-            for (day, usage) in days {
-                let total = usage.input + usage.output;
-                println!(
-                    "{:<15} {:<15} {:<15} {}",
-                    day,
-                    format_tokens(usage.input),
-                    format_tokens(usage.output),
-                    format_tokens(total)
-                );
-            }
-        }
-        _ => {
-            println!("Unknown grouping: {}", group_by);
+    for (keys, usage) in rows {
+        let mut line = String::new();
+        for (key, width) in keys.iter().zip(&widths) {
+            line.push_str(&format!("{:<width$}", key, width = width));
         }
+        println!(
+            "{}{:<10} {:<10} {:<10} {:<10} {}",
+            line,
+            format_tokens(usage.input),
+            format_tokens(usage.output),
+            format_tokens(usage.cache),
+            format_tokens(usage.total()),
+            cost::format_cost(usage.cost)
+        );
     }
 
     println!();
     println!("Total: {} tokens across {} sessions",
         format_tokens(stats.total_input + stats.total_output),
-        stats.session_count
+        stats.sessions.len()
     );
+    let subagents = stats.subagent_input + stats.subagent_output;
+    if subagents > 0 {
+        println!("  of which subagents: {} ({} in, {} out)",
+            format_tokens(subagents),
+            format_tokens(stats.subagent_input),
+            format_tokens(stats.subagent_output)
+        );
+    }
+    println!();
 
-    Ok(())
+    print_model_breakdown(stats);
+}
+
+fn report_json(days: u32, group_by: &[Grouping], rows: &[(&[String], &GroupUsage)]) -> Value {
+    let usage_json = |usage: &GroupUsage| {
+        json!({
+            "input_tokens": usage.input,
+            "output_tokens": usage.output,
+            "cache_tokens": usage.cache,
+            "total_tokens": usage.total(),
+            "cost_usd": (usage.cost * 10_000.0).round() / 10_000.0,
+        })
+    };
+    let total = usage_json(&report_total(rows));
+    let rows: Vec<Value> = rows
+        .iter()
+        .map(|(keys, usage)| {
+            let mut row = usage_json(usage);
+            for (grouping, key) in group_by.iter().zip(keys.iter()) {
+                row[grouping.title().to_lowercase()] = json!(key);
+            }
+            row
+        })
+        .collect();
+    json!({
+        "days": days,
+        "group_by": group_by.iter().map(|g| g.title().to_lowercase()).collect::<Vec<_>>(),
+        "total": total,
+        "rows": rows,
+    })
+}
+
+fn report_csv(group_by: &[Grouping], rows: &[(&[String], &GroupUsage)]) -> String {
+    let mut header: Vec<String> = group_by.iter().map(|g| g.title().to_lowercase()).collect();
+    header.extend(["input_tokens", "output_tokens", "cache_tokens", "total_tokens", "cost_usd"].map(String::from));
+    let mut out = header.join(",") + "\n";
+
+    for (keys, usage) in rows {
+        let mut fields: Vec<String> = keys.iter().map(|k| csv_field(k)).collect();
+        fields.extend([
+            usage.input.to_string(),
+            usage.output.to_string(),
+            usage.cache.to_string(),
+            usage.total().to_string(),
+            format!("{:.4}", usage.cost),
+        ]);
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn report_markdown(days: u32, group_by: &[Grouping], rows: &[(&[String], &GroupUsage)]) -> String {
+    let mut out = format!("# Usage Report\n\nLast {} days\n\n", days);
+
+    let titles: Vec<&str> = group_by.iter().map(|g| g.title()).collect();
+    out.push_str(&format!("| {} | Input | Output | Cache | Total | Cost |\n", titles.join(" | ")));
+    out.push_str(&format!("|{}---|---|---|---|---|\n", "---|".repeat(titles.len())));
+
+    let mut line = |keys: Vec<String>, usage: &GroupUsage| {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            keys.join(" | "),
+            format_tokens(usage.input),
+            format_tokens(usage.output),
+            format_tokens(usage.cache),
+            format_tokens(usage.total()),
+            cost::format_cost(usage.cost)
+        ));
+    };
+    for (keys, usage) in rows {
+        line(keys.iter().map(|k| md_cell(k)).collect(), usage);
+    }
+    let mut total_keys = vec!["**Total**".to_string()];
+    total_keys.resize(group_by.len(), String::new());
+    line(total_keys, &report_total(rows));
+    out
+}
+
+/// Print tokens per profile, with Codex as its own row
+fn print_profile_breakdown(stats: &UsageStats) {
+    println!("By Profile:");
+    let mut profiles: Vec<_> = stats.by_profile.iter().collect();
+    profiles.sort_by(|a, b| b.1.cmp(a.1));
+    for (profile, tokens) in profiles {
+        println!("  {:<28} {}", profile, format_tokens(*tokens));
+    }
+    println!();
+}
+
+/// Print tokens and cost per model
+fn print_model_breakdown(stats: &UsageStats) {
+    if stats.by_model.is_empty() {
+        return;
+    }
+
+    println!("By Model:");
+    println!("  {:<24} {:<12} {:<12} {:<12} Cost", "Model", "Input", "Output", "Cache");
+
+    let mut models: Vec<_> = stats.by_model.iter().collect();
+    models.sort_by(|a, b| b.1.cost_usd(b.0).total_cmp(&a.1.cost_usd(a.0)));
+
+    let mut total_cost = 0.0;
+    for (model, usage) in models {
+        let model_cost = usage.cost_usd(model);
+        total_cost += model_cost;
+        println!(
+            "  {:<24} {:<12} {:<12} {:<12} {}",
+            cost::short_model_name(model),
+            format_tokens(usage.input_tokens),
+            format_tokens(usage.output_tokens),
+            format_tokens(usage.cache_read_tokens + usage.cache_write_tokens()),
+            cost::format_cost(model_cost)
+        );
+    }
+    println!("  {:<63} {}", "Total", cost::format_cost(total_cost));
+    println!();
 }
 
 fn reset_usage(confirm: bool) -> Result<()> {
     if !confirm {
-        println!("This will reset all usage tracking data.");
+        println!("This will remove all budget limits, project limits, enforcement and any bypass,");
+        println!("along with the budget hook in Claude Code's settings.");
         println!("Run with --confirm to proceed.");
         return Ok(());
     }
 
-    let config = BudgetConfig::default();
-    save_config(&config)?;
+    // With enforcement gone the hook has nothing left to check
+    let profile = profile::single()?;
+    hooks::wire_budget_hooks(&profile.settings_path(), &[], true)?;
+    save_config(profile, &BudgetConfig::default())?;
 
-    println!("Budget configuration reset.");
+    println!("Budget limits, project limits, enforcement and bypass reset.");
     println!("Note: Historical usage in JSONL files is not deleted.");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_first_day() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // A Sunday at the end of a month
+        let today = day(2026, 5, 31);
+
+        assert_eq!(Period::Day.first_day(today), today);
+        assert_eq!(Period::Week.first_day(today), day(2026, 5, 25));
+        assert_eq!(Period::Week.first_day(day(2026, 5, 25)), day(2026, 5, 25));
+        assert_eq!(Period::Month.first_day(today), day(2026, 5, 1));
+    }
+
+    #[test]
+    fn test_verdict() {
+        let usage = |period, used: f64, limit: f64| LimitUse {
+            period,
+            project: None,
+            usd: false,
+            amounts: format!("{}/{} tokens", used, limit),
+            used,
+            limit,
+        };
+        let daily_over = [usage(Period::Day, 120.0, 100.0), usage(Period::Week, 120.0, 1000.0)];
+        let weekly_near = [usage(Period::Day, 50.0, 100.0), usage(Period::Week, 850.0, 1000.0)];
+
+        assert_eq!(verdict(Enforcement::Off, None, &daily_over), Verdict::Allow);
+        assert_eq!(
+            verdict(Enforcement::Warn, None, &daily_over),
+            Verdict::Warn("Budget exceeded: daily limit (120/100 tokens).".to_string())
+        );
+        assert!(matches!(verdict(Enforcement::Block, None, &daily_over), Verdict::Block(r) if r.contains("budget bypass")));
+
+        // Soft limits only warn, even when blocking
+        assert_eq!(verdict(Enforcement::Block, None, &weekly_near), Verdict::Allow);
+        assert_eq!(
+            verdict(Enforcement::Block, Some(80), &weekly_near),
+            Verdict::Warn("Budget nearly used: 85% of the weekly limit (850/1000 tokens).".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alerts_once_per_period() {
+        let gauge = |key: &str, percent| Gauge {
+            label: "Daily".to_string(),
+            amounts: format!("{}/100 tokens", percent),
+            percent,
+            key: key.to_string(),
+        };
+        let thresholds = [50, 80, 100];
+        let mut fired = BTreeMap::new();
+
+        // Only the highest threshold passed alerts, and only once
+        let alerts = update_alerts(&mut fired, &[gauge("d:1", 85.0)], &thresholds);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].title, "Budget: 80% of daily limit");
+        assert!(update_alerts(&mut fired, &[gauge("d:1", 90.0)], &thresholds).is_empty());
+
+        let alerts = update_alerts(&mut fired, &[gauge("d:1", 120.0)], &thresholds);
+        assert!(alerts[0].exceeded);
+
+        // The next period starts over, and the last one is forgotten
+        assert_eq!(update_alerts(&mut fired, &[gauge("d:2", 60.0)], &thresholds).len(), 1);
+        assert_eq!(fired.keys().collect::<Vec<_>>(), ["d:2"]);
+    }
+
+    #[test]
+    fn test_project_limits_match_directories() {
+        let mut stats = UsageStats::default();
+        for (dir, tokens) in [("/work/client-a/api", 100), ("/work/client-a/web/src", 20), ("/work/client-b", 7)] {
+            let usage = ModelUsage { input_tokens: tokens, ..Default::default() };
+            stats.by_dir.entry(dir.to_string()).or_default().insert("unknown".to_string(), usage);
+        }
+
+        assert_eq!(stats.totals(Some("/work/client-a/*")).0, 120);
+        assert_eq!(stats.totals(Some("/work/client-a/web")).0, 20);
+        assert_eq!(stats.totals(Some("/work/client-*")).0, 127);
+        // A glob component stops at the path separator
+        assert_eq!(stats.totals(Some("/work/*/src")).0, 0);
+        assert_eq!(stats.totals(Some("/work/client")).0, 0);
+        assert!(project_pattern("work/client-a").is_err());
+    }
+
+    #[test]
+    fn test_usage_since_each_start() {
+        let now = Utc::now();
+        let hours_ago = |h| now - Duration::hours(h);
+        let mut stats = UsageStats {
+            since: vec![(hours_ago(2), UsageStats::default()), (hours_ago(48), UsageStats::default())],
+            ..Default::default()
+        };
+        let entries: Vec<_> = [(1, 10, None), (30, 20, None), (100, 40, Some("/home/u/other"))]
+            .into_iter()
+            .map(|(h, tokens, cwd)| UsageEntry {
+                timestamp: Some(hours_ago(h)),
+                model: "opus".to_string(),
+                usage: ModelUsage { input_tokens: tokens, ..Default::default() },
+                cwd: cwd.map(str::to_string),
+                ..Default::default()
+            })
+            .collect();
+        let transcript = Transcript {
+            provider: "claude",
+            profile: "default",
+            project_name: "-work-api",
+            session: "s".to_string(),
+            cwd: Some("/work/api".to_string()),
+            subagent: false,
+            modified: SystemTime::now(),
+        };
+        add_transcript(&mut stats, transcript, &entries, hours_ago(100));
+
+        assert_eq!(stats.total_tokens(), 70);
+        // Each line counts in the directory it records, else the transcript's
+        assert_eq!(stats.totals(Some("/work")).0, 30);
+        assert_eq!(stats.totals(Some("/home/u")).0, 40);
+        let totals: Vec<_> = stats.since.iter().map(|(_, s)| s.totals(Some("/work")).0).collect();
+        assert_eq!(totals, [10, 30]);
+    }
+
+    #[test]
+    fn test_report_rows_order() {
+        let mut stats = UsageStats {
+            grouping: vec![Grouping::Model, Grouping::Weekday],
+            ..Default::default()
+        };
+        for (model, day, tokens) in [("haiku", "Sun", 50), ("opus", "Tue", 30), ("opus", "Mon", 40), ("haiku", "Mon", 10)] {
+            let usage = ModelUsage { input_tokens: tokens, ..Default::default() };
+            let key = vec![model.to_string(), day.to_string()];
+            stats.by_group.entry(key).or_default().add(model, &usage);
+        }
+
+        // Models by their total, then days of the week in order
+        let rows = report_rows(&stats);
+        let keys: Vec<_> = rows.iter().map(|(keys, _)| keys.join(" ")).collect();
+        assert_eq!(keys, ["opus Mon", "opus Tue", "haiku Mon", "haiku Sun"]);
+
+        let csv = report_csv(&stats.grouping, &rows);
+        assert!(csv.starts_with("model,weekday,input_tokens,"));
+        assert!(csv.lines().nth(1).unwrap().starts_with("opus,Mon,40,0,0,40,"));
+    }
+
+    #[test]
+    fn test_project_enforcement() {
+        let mut config = BudgetConfig { enforce: Enforcement::Block, ..Default::default() };
+        config.projects.insert("/work".to_string(), Enforcement::Warn);
+        config.projects.insert("/work/spike".to_string(), Enforcement::Off);
+        config.projects.insert("/work/client-*/scratch".to_string(), Enforcement::Off);
+
+        assert_eq!(config.enforcement_for(Path::new("/home/u/app")), Enforcement::Block);
+        assert_eq!(config.enforcement_for(Path::new("/work/client")), Enforcement::Warn);
+        assert_eq!(config.enforcement_for(Path::new("/work/spike/src")), Enforcement::Off);
+        // Globs match as they do for project limits
+        assert_eq!(config.enforcement_for(Path::new("/work/client-a/scratch/x")), Enforcement::Off);
+        assert_eq!(config.enforcement_for(Path::new("/work/client-a/api")), Enforcement::Warn);
+        // A sibling sharing a prefix is not inside the project
+        assert_eq!(config.enforcement_for(Path::new("/workshop")), Enforcement::Block);
+        assert!(config.enforces());
+    }
+}
@@ -0,0 +1,87 @@
+//! User configuration for coder-tools
+//!
+//! Loaded from ~/.config/coder-tools/config.json. Every field is optional;
+//! a missing file means defaults everywhere.
+
+use crate::pricing::ModelPricing;
+use crate::providers::custom::AgentConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Directory for monitor stats exports (supports ~/)
+    pub export_dir: Option<String>,
+    /// Formats written by the in-TUI export: json, csv, markdown
+    pub export_formats: Option<Vec<String>>,
+    /// Per-model pricing overrides, keyed by model-name prefix
+    pub pricing: Option<BTreeMap<String, ModelPricing>>,
+    /// Add missing status hooks to agent settings when the monitor starts (default: true)
+    pub auto_install_hooks: Option<bool>,
+    /// Extra agents to detect alongside the built-in providers
+    pub agents: Vec<AgentConfig>,
+    /// Claude Code config directories by profile name (supports ~/)
+    pub profiles: BTreeMap<String, String>,
+    /// Percentages of a budget limit at which the monitor alerts (default: 50, 80, 100)
+    pub budget_alerts: Option<Vec<u8>>,
+}
+
+impl Config {
+    /// Budget alert thresholds, in percent
+    pub fn budget_alerts(&self) -> Vec<u8> {
+        self.budget_alerts.clone().unwrap_or_else(|| vec![50, 80, 100])
+    }
+
+    /// Resolved export directory (defaults to ~/.local/share/coder-tools/exports)
+    pub fn export_dir(&self) -> PathBuf {
+        match self.export_dir {
+            Some(ref dir) => expand_home(dir),
+            None => dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("coder-tools")
+                .join("exports"),
+        }
+    }
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    let dir = dirs::config_dir().context("Could not determine config directory")?;
+    Ok(dir.join("coder-tools").join("config.json"))
+}
+
+/// Load the config file, falling back to defaults if it doesn't exist
+pub fn load() -> Result<Config> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid config: {}", path.display()))
+}
+
+/// The config, loaded once per process
+///
+/// Agents, pricing and profiles fall back to defaults when it can't be
+/// read; `main` reports the error once, so they don't have to.
+pub fn shared() -> Result<&'static Config> {
+    static CONFIG: OnceLock<Result<Config, String>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| load().map_err(|e| format!("{:#}", e)))
+        .as_ref()
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Expand a leading ~/ to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
@@ -1,9 +1,63 @@
-//! Token counting and cost calculation for Claude sessions
+//! Token counting and cost calculation for agent sessions
 //!
-//! Reads JSONL session files from ~/.claude/projects/{path_hash}/*.jsonl
+//! Where transcripts live and how their lines are read is up to each
+//! provider (see `providers`); this module accumulates and prices the usage.
 
+use crate::pricing::{self, ModelPricing};
+use crate::providers;
+use crate::usage_index;
+use crate::mux::Pane;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Token counts for a single model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_5m_tokens: u64,
+    pub cache_write_1h_tokens: u64,
+}
+
+impl ModelUsage {
+    pub fn add(&mut self, other: &ModelUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_5m_tokens += other.cache_write_5m_tokens;
+        self.cache_write_1h_tokens += other.cache_write_1h_tokens;
+    }
+
+    pub fn cache_write_tokens(&self) -> u64 {
+        self.cache_write_5m_tokens + self.cache_write_1h_tokens
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// Cost in USD at the given per-million rates
+    pub fn cost_with(&self, pricing: &ModelPricing) -> f64 {
+        let per_token = |tokens: u64, rate: f64| tokens as f64 / 1_000_000.0 * rate;
+        per_token(self.input_tokens, pricing.input)
+            + per_token(self.output_tokens, pricing.output)
+            + per_token(self.cache_read_tokens, pricing.cache_read)
+            + per_token(self.cache_write_5m_tokens, pricing.cache_write_5m)
+            + per_token(self.cache_write_1h_tokens, pricing.cache_write_1h)
+    }
+
+    /// Cost in USD for `model` from the pricing table
+    pub fn cost_usd(&self, model: &str) -> f64 {
+        self.cost_with(&pricing::table().lookup(model))
+    }
+}
 
 /// Token usage for a session
 #[derive(Debug, Clone, Default)]
@@ -12,127 +66,382 @@ pub struct TokenUsage {
     pub output_tokens: u64,
     pub cache_read_tokens: u64,
     pub cache_write_tokens: u64,
+    /// Breakdown by `message.model`
+    pub by_model: BTreeMap<String, ModelUsage>,
 }
 
 impl TokenUsage {
-    /// Calculate cost in USD based on Claude pricing
-    /// Pricing as of 2024: Sonnet 3.5
-    /// - Input: $3 / 1M tokens
-    /// - Output: $15 / 1M tokens
-    /// - Cache read: $0.30 / 1M tokens
-    /// - Cache write: $3.75 / 1M tokens
+    /// Add usage attributed to `model`
+    pub fn add(&mut self, model: &str, usage: &ModelUsage) {
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cache_read_tokens += usage.cache_read_tokens;
+        self.cache_write_tokens += usage.cache_write_tokens();
+        self.by_model.entry(model.to_string()).or_default().add(usage);
+    }
+
+    /// Calculate cost in USD, pricing each model from the pricing table.
+    /// Totals without a model breakdown are priced at the default rates.
     pub fn cost_usd(&self) -> f64 {
-        let input_cost = (self.input_tokens as f64 / 1_000_000.0) * 3.0;
-        let output_cost = (self.output_tokens as f64 / 1_000_000.0) * 15.0;
-        let cache_read_cost = (self.cache_read_tokens as f64 / 1_000_000.0) * 0.30;
-        let cache_write_cost = (self.cache_write_tokens as f64 / 1_000_000.0) * 3.75;
-        input_cost + output_cost + cache_read_cost + cache_write_cost
+        if self.by_model.is_empty() {
+            let usage = ModelUsage {
+                input_tokens: self.input_tokens,
+                output_tokens: self.output_tokens,
+                cache_read_tokens: self.cache_read_tokens,
+                cache_write_5m_tokens: self.cache_write_tokens,
+                cache_write_1h_tokens: 0,
+            };
+            return usage.cost_with(&pricing::DEFAULT_PRICING);
+        }
+
+        self.by_model
+            .iter()
+            .map(|(model, usage)| usage.cost_usd(model))
+            .sum()
     }
 
     pub fn total_tokens(&self) -> u64 {
         self.input_tokens + self.output_tokens
     }
+
+    /// Add all of another session's usage (e.g. a subagent's)
+    pub fn absorb(&mut self, other: &TokenUsage) {
+        for (model, usage) in &other.by_model {
+            self.add(model, usage);
+        }
+    }
 }
 
-/// Get Claude projects directory
-fn claude_projects_dir() -> Option<PathBuf> {
-    dirs::home_dir().map(|h| h.join(".claude").join("projects"))
+/// Shorten a model id for display (e.g. "claude-opus-4-1-20250805" -> "opus-4-1")
+pub fn short_model_name(model: &str) -> String {
+    let name = model.strip_prefix("claude-").unwrap_or(model);
+    match name.rsplit_once('-') {
+        Some((head, date)) if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) => {
+            head.to_string()
+        }
+        _ => name.to_string(),
+    }
 }
 
-/// Hash a path the way Claude does (replace / and _ with -)
-fn hash_path(path: &str) -> String {
-    path.replace('/', "-").replace('_', "-")
+/// Model recorded for usage that does not name its model
+pub const UNKNOWN_MODEL: &str = "unknown";
+
+/// Usage recorded by one transcript line, for accounting by time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// When the line was written, if it says
+    pub timestamp: Option<DateTime<Utc>>,
+    pub model: String,
+    pub usage: ModelUsage,
+    /// Message and request id; lines sharing one repeat the same usage
+    pub id: Option<String>,
+    /// Written by a subagent on behalf of the session
+    pub sidechain: bool,
+    /// Session the line belongs to, as recorded on the line
+    pub session_id: Option<String>,
+    /// Working directory of the agent when it wrote the line
+    pub cwd: Option<String>,
+}
+
+/// The `timestamp` field that Claude and Codex put on every JSONL line
+pub fn line_timestamp(json: &serde_json::Value) -> Option<DateTime<Utc>> {
+    let text = json.get("timestamp")?.as_str()?;
+    DateTime::parse_from_rfc3339(text).ok().map(|t| t.with_timezone(&Utc))
 }
 
-/// Find JSONL session files for a given working directory
-fn find_session_files(working_dir: &str) -> Vec<PathBuf> {
-    let projects_dir = match claude_projects_dir() {
-        Some(d) => d,
-        None => return Vec::new(),
-    };
+/// Reads an append-only transcript line by line, keeping any state it needs
+/// between lines (e.g. the current model, or the last cumulative total)
+pub trait LineParser: Send {
+    fn add_line(&mut self, usage: &mut TokenUsage, line: &str);
+}
 
-    let path_hash = hash_path(working_dir);
-    let session_dir = projects_dir.join(&path_hash);
+/// Creates a fresh parser for each transcript
+pub type ParserFactory = Arc<dyn Fn() -> Box<dyn LineParser> + Send + Sync>;
 
-    if !session_dir.exists() {
-        return Vec::new();
+/// How a provider's transcripts record token usage
+#[derive(Clone)]
+pub enum UsageFormat {
+    /// Append-only lines, tailed incrementally with a fresh parser per file
+    Lines(ParserFactory),
+    /// A document rewritten in place, reparsed whenever it changes
+    Snapshot(fn(&Path) -> TokenUsage),
+}
+
+impl UsageFormat {
+    /// Sum token usage over a whole transcript
+    pub fn parse_file(&self, path: &Path) -> TokenUsage {
+        match self {
+            UsageFormat::Lines(new_parser) => {
+                let mut usage = TokenUsage::default();
+                let mut parser = new_parser();
+                if let Ok(content) = fs::read_to_string(path) {
+                    for line in content.lines() {
+                        parser.add_line(&mut usage, line);
+                    }
+                }
+                usage
+            }
+            UsageFormat::Snapshot(parse) => parse(path),
+        }
     }
+}
 
-    fs::read_dir(&session_dir)
-        .ok()
-        .map(|entries| {
-            entries
-                .filter_map(|e| e.ok())
-                .map(|e| e.path())
-                .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
-                .collect()
-        })
-        .unwrap_or_default()
+/// How far an append-only transcript has been read, so that later reads
+/// only see the lines appended since
+///
+/// Shared by `UsageTracker` and the persistent `usage_index`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FileCursor {
+    /// File size and mtime at the last read, to skip unchanged files
+    len: u64,
+    modified: Option<SystemTime>,
+    /// File identity, to detect rotation (replaced by a new file)
+    file_id: Option<u64>,
+    /// Byte offset just past the last complete line consumed
+    offset: u64,
+    /// Hash of the bytes just before `offset`, to detect a rewrite in place
+    #[serde(default)]
+    tail: u64,
 }
 
-/// Parse token usage from a single JSONL file
-fn parse_jsonl_tokens(path: &Path) -> TokenUsage {
-    let mut usage = TokenUsage::default();
+/// What happened to a transcript since it was last read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Unchanged,
+    /// Lines may have been appended
+    Grown,
+    /// Truncated, replaced or rewritten in place: everything read so far is
+    /// void and the file is read again from the start
+    Rewritten,
+}
 
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return usage,
-    };
+impl FileCursor {
+    /// Compare the file with what was read last, resetting the cursor when
+    /// the file has to be read again from the start
+    pub fn check(&mut self, path: &Path) -> std::io::Result<FileChange> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok();
+        let file_id = file_id(&metadata);
 
-    for line in content.lines() {
-        if line.trim().is_empty() {
-            continue;
+        if self.file_id.is_some() && self.file_id == file_id && self.len == metadata.len() && self.modified == modified
+        {
+            return Ok(FileChange::Unchanged);
+        }
+        if self.offset == 0 {
+            return Ok(FileChange::Grown);
+        }
+        let went_back = matches!((modified, self.modified), (Some(now), Some(before)) if now < before);
+        if metadata.len() < self.offset
+            || self.file_id != file_id
+            || went_back
+            || tail_hash(path, self.offset).ok() != Some(self.tail)
+        {
+            *self = FileCursor::default();
+            return Ok(FileChange::Rewritten);
         }
+        Ok(FileChange::Grown)
+    }
 
-        // Parse JSON and extract usage field (nested inside "message")
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-            // Usage is nested: {"message": {"usage": {...}}}
-            let usage_obj = json
-                .get("message")
-                .and_then(|m| m.get("usage"));
+    /// Pass each complete line appended since the last read to `f`, returning
+    /// the bytes consumed
+    ///
+    /// A trailing partial line (still being written) is left for the next
+    /// read.
+    pub fn read_lines(&mut self, path: &Path, mut f: impl FnMut(&str)) -> std::io::Result<u64> {
+        // Taken before reading, so a write in between shows as a change next time
+        let metadata = fs::metadata(path)?;
 
-            if let Some(usage_obj) = usage_obj {
-                if let Some(input) = usage_obj.get("input_tokens").and_then(|v| v.as_u64()) {
-                    usage.input_tokens += input;
-                }
-                if let Some(output) = usage_obj.get("output_tokens").and_then(|v| v.as_u64()) {
-                    usage.output_tokens += output;
-                }
-                if let Some(cache_read) = usage_obj.get("cache_read_input_tokens").and_then(|v| v.as_u64()) {
-                    usage.cache_read_tokens += cache_read;
-                }
-                if let Some(cache_write) = usage_obj.get("cache_creation_input_tokens").and_then(|v| v.as_u64()) {
-                    usage.cache_write_tokens += cache_write;
-                }
+        let mut file = fs::File::open(path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+
+        let complete = match buf.iter().rposition(|&b| b == b'\n') {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+        if complete > 0 {
+            for line in buf[..complete].split(|&b| b == b'\n') {
+                f(&String::from_utf8_lossy(line));
             }
         }
+        self.offset += complete as u64;
+        self.tail = tail_hash(path, self.offset).unwrap_or_default();
+        self.len = metadata.len();
+        self.modified = metadata.modified().ok();
+        self.file_id = file_id(&metadata);
+        Ok(complete as u64)
     }
+}
 
-    usage
+/// Read position and running totals for one transcript
+struct TranscriptCursor {
+    file: FileCursor,
+    parser: Box<dyn LineParser>,
+    usage: TokenUsage,
 }
 
-/// Get token usage for a Claude session in the given working directory
-pub fn get_claude_usage(working_dir: &str) -> TokenUsage {
-    let files = find_session_files(working_dir);
+impl TranscriptCursor {
+    fn new(parser: Box<dyn LineParser>) -> Self {
+        Self {
+            file: FileCursor::default(),
+            parser,
+            usage: TokenUsage::default(),
+        }
+    }
+}
 
-    // Get the most recent session file (by modification time)
-    let most_recent = files
-        .into_iter()
-        .filter_map(|p| {
-            fs::metadata(&p)
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .map(|t| (p, t))
-        })
-        .max_by_key(|(_, t)| *t)
-        .map(|(p, _)| p);
+/// Incremental token accounting over transcript files
+///
+/// Keeps a byte offset and running totals per transcript so each update
+/// only parses lines appended since the last one. Files that shrink, are
+/// replaced or are rewritten in place are re-read from the start.
+#[derive(Default)]
+pub struct UsageTracker {
+    cursors: HashMap<PathBuf, TranscriptCursor>,
+    /// Totals for whole-file formats (Gemini chats), keyed by size and mtime
+    snapshots: HashMap<PathBuf, (u64, Option<SystemTime>, TokenUsage)>,
+}
 
-    match most_recent {
-        Some(path) => parse_jsonl_tokens(&path),
-        None => TokenUsage::default(),
+/// Providers whose transcripts we can read token usage from
+pub fn supports_usage(provider: &str) -> bool {
+    providers::get(provider)
+        .map(|p| p.usage_format().is_some())
+        .unwrap_or(false)
+}
+
+impl UsageTracker {
+    /// Current totals for a transcript, reading only newly appended lines
+    fn usage_for(&mut self, path: &Path, new_parser: &dyn Fn() -> Box<dyn LineParser>) -> TokenUsage {
+        let cursor = self
+            .cursors
+            .entry(path.to_path_buf())
+            .or_insert_with(|| TranscriptCursor::new(new_parser()));
+
+        match cursor.file.check(path) {
+            Ok(FileChange::Unchanged) => return cursor.usage.clone(),
+            Ok(FileChange::Grown) => {}
+            Ok(FileChange::Rewritten) => {
+                cursor.parser = new_parser();
+                cursor.usage = TokenUsage::default();
+            }
+            Err(_) => {
+                self.cursors.remove(path);
+                return TokenUsage::default();
+            }
+        }
+
+        let TranscriptCursor { file, parser, usage } = cursor;
+        let _ = file.read_lines(path, |line| parser.add_line(usage, line));
+        usage.clone()
+    }
+
+    /// Totals for a file that must be parsed whole, reparsed only when it changes
+    fn snapshot_usage(&mut self, path: &Path, parse: fn(&Path) -> TokenUsage) -> TokenUsage {
+        let metadata = match fs::metadata(path) {
+            Ok(m) => m,
+            Err(_) => {
+                self.snapshots.remove(path);
+                return TokenUsage::default();
+            }
+        };
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+
+        if let Some((cached_len, cached_modified, usage)) = self.snapshots.get(path) {
+            if *cached_len == len && *cached_modified == modified {
+                return usage.clone();
+            }
+        }
+
+        let usage = parse(path);
+        self.snapshots
+            .insert(path.to_path_buf(), (len, modified, usage.clone()));
+        usage
+    }
+
+    /// Token usage for an agent session, with the transcript it was read from
+    pub fn session_usage(&mut self, provider: &str, session: &SessionRef) -> (Option<PathBuf>, TokenUsage) {
+        let provider = match providers::get(provider) {
+            Some(p) => p,
+            None => return (None, TokenUsage::default()),
+        };
+        let (path, format) = match (provider.locate_transcript(session), provider.usage_format()) {
+            (Some(path), Some(format)) => (path, format),
+            _ => return (None, TokenUsage::default()),
+        };
+
+        // Indexed transcripts are already read up to date
+        if let Some(usage) = usage_index::session_usage(provider, &path) {
+            return (Some(path), usage);
+        }
+
+        let usage = match format {
+            UsageFormat::Lines(new_parser) => {
+                let mut usage = self.usage_for(&path, new_parser.as_ref());
+                // Subagents' usage is part of the session that started them
+                for subagent in provider.subagent_transcripts(&path) {
+                    usage.absorb(&self.usage_for(&subagent, new_parser.as_ref()));
+                }
+                usage
+            }
+            UsageFormat::Snapshot(parse) => self.snapshot_usage(&path, parse),
+        };
+        (Some(path), usage)
+    }
+}
+
+/// What a pane knows about its agent session
+#[derive(Debug, Clone, Default)]
+pub struct SessionRef {
+    pub working_dir: String,
+    /// Published by hooks as @agent_session_id
+    pub session_id: Option<String>,
+    /// Published by hooks as @agent_transcript
+    pub transcript_path: Option<String>,
+}
+
+impl SessionRef {
+    pub fn from_pane(pane: &Pane) -> Self {
+        Self {
+            // The shell may have moved on from where the agent was started
+            working_dir: pane.agent_cwd.clone().unwrap_or_else(|| pane.current_path.clone()),
+            session_id: pane.agent_session_id.clone(),
+            transcript_path: pane.agent_transcript.clone(),
+        }
     }
 }
 
+/// Bytes before a read offset that `tail_hash` covers
+const TAIL_BYTES: u64 = 256;
+
+/// Hash (FNV-1a) of the bytes just before `offset`, the last ones read
+///
+/// A transcript rewritten in place keeps its inode and may be as long as
+/// before, but hardly ever these bytes, so a changed hash means the file
+/// has to be read again.
+fn tail_hash(path: &Path, offset: u64) -> std::io::Result<u64> {
+    let start = offset.saturating_sub(TAIL_BYTES);
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::new();
+    file.take(offset - start).read_to_end(&mut buf)?;
+    Ok(buf
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3)))
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<u64> {
+    Some(0)
+}
+
 /// Format tokens for display (e.g., "12.3k" or "1.2M")
 pub fn format_tokens(tokens: u64) -> String {
     if tokens >= 1_000_000 {
@@ -146,9 +455,7 @@ pub fn format_tokens(tokens: u64) -> String {
 
 /// Format cost for display
 pub fn format_cost(cost: f64) -> String {
-    if cost >= 1.0 {
-        format!("${:.2}", cost)
-    } else if cost >= 0.01 {
+    if cost >= 0.01 {
         format!("${:.2}", cost)
     } else if cost > 0.0 {
         format!("${:.3}", cost)
@@ -168,6 +475,7 @@ mod tests {
             output_tokens: 100_000,
             cache_read_tokens: 500_000,
             cache_write_tokens: 0,
+            by_model: BTreeMap::new(),
         };
         // 1M input @ $3/M = $3
         // 100k output @ $15/M = $1.5
@@ -176,6 +484,51 @@ mod tests {
         assert!((usage.cost_usd() - expected).abs() < 0.001);
     }
 
+    #[test]
+    fn test_short_model_name() {
+        assert_eq!(short_model_name("claude-opus-4-1-20250805"), "opus-4-1");
+        assert_eq!(short_model_name("claude-sonnet-4-5"), "sonnet-4-5");
+        assert_eq!(short_model_name("gpt-5"), "gpt-5");
+    }
+
+    #[test]
+    fn test_usage_tracker_incremental() {
+        use crate::providers::claude;
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("coder-tools-tail-{}.jsonl", std::process::id()));
+        let line = |input: u64| {
+            format!("{{\"message\":{{\"usage\":{{\"input_tokens\":{},\"output_tokens\":1}}}}}}\n", input)
+        };
+        fs::write(&path, line(10)).unwrap();
+
+        let mut tracker = UsageTracker::default();
+        assert_eq!(tracker.usage_for(&path, &claude::line_parser).input_tokens, 10);
+
+        // Appended lines are added; a partial trailing line waits for its newline
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(line(5).as_bytes()).unwrap();
+        file.write_all(b"{\"message\":{\"usage\":{\"input_tok").unwrap();
+        file.flush().unwrap();
+        assert_eq!(tracker.usage_for(&path, &claude::line_parser).input_tokens, 15);
+        file.write_all(b"ens\":7}}}\n").unwrap();
+        file.flush().unwrap();
+        let usage = tracker.usage_for(&path, &claude::line_parser);
+        assert_eq!(usage.input_tokens, 22);
+        assert_eq!(usage.output_tokens, 2);
+
+        // Truncation resets the running totals
+        fs::write(&path, line(3)).unwrap();
+        assert_eq!(tracker.usage_for(&path, &claude::line_parser).input_tokens, 3);
+
+        // So does a rewrite in place that leaves the file longer
+        fs::write(&path, line(4) + &line(6)).unwrap();
+        assert_eq!(tracker.usage_for(&path, &claude::line_parser).input_tokens, 10);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tracker.usage_for(&path, &claude::line_parser).input_tokens, 0);
+    }
+
     #[test]
     fn test_format_tokens() {
         assert_eq!(format_tokens(500), "500");
@@ -3,10 +3,11 @@
 //! Status is determined from hook-published tmux pane options (@agent_status, @agent_task).
 //! No screen scraping or file parsing required.
 
+use crate::providers;
 use std::fmt;
 
 /// Status of an AI coding session
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Status {
     /// Waiting for user input
     WaitingForInput,
@@ -15,6 +16,7 @@ pub enum Status {
     /// Actively working (thinking, tool execution)
     Working,
     /// Not a recognized AI session
+    #[default]
     NotDetected,
 }
 
@@ -48,12 +50,6 @@ impl Status {
     }
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Status::NotDetected
-    }
-}
-
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} {}", self.icon(), self.label())
@@ -108,31 +104,9 @@ impl DetectionResult {
 
 /// Check if the agent is actually running based on pane_current_command
 fn is_agent_running(provider: &str, command: &str) -> bool {
-    match provider {
-        "claude" => {
-            // Claude Code shows as version number (e.g., "2.1.6", "2.1.7")
-            // or "claude" or "node" depending on how it was launched
-            is_version_string(command) || command == "claude" || command == "node"
-        }
-        "gemini" => {
-            command == "gemini" || command == "node"
-        }
-        "codex" => {
-            // Codex binary may show as "codex" or "codex-aarch64-..." etc.
-            command.starts_with("codex") || command == "node"
-        }
-        _ => false,
-    }
-}
-
-/// Check if string looks like a version number (e.g., "2.1.6")
-fn is_version_string(s: &str) -> bool {
-    if s.is_empty() {
-        return false;
-    }
-    // Version strings: digits and dots, starting with a digit
-    s.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false)
-        && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+    providers::get(provider)
+        .map(|p| p.is_running(command))
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
@@ -175,16 +149,6 @@ mod tests {
         assert_eq!(result.task, None);
     }
 
-    #[test]
-    fn test_is_version_string() {
-        assert!(is_version_string("2.1.6"));
-        assert!(is_version_string("2.1.7"));
-        assert!(is_version_string("1.0.0"));
-        assert!(!is_version_string("fish"));
-        assert!(!is_version_string("node"));
-        assert!(!is_version_string(""));
-    }
-
     #[test]
     fn test_is_agent_running() {
         // Claude with version string
@@ -205,5 +169,15 @@ mod tests {
         assert!(is_agent_running("codex", "codex-aarch64-a"));
         assert!(is_agent_running("codex", "node"));
         assert!(!is_agent_running("codex", "fish"));
+
+        // Aider
+        assert!(is_agent_running("aider", "aider"));
+        // A pane running it through Python is named by the script, so a bare
+        // interpreter is something else
+        assert!(!is_agent_running("aider", "python3.12"));
+        assert!(!is_agent_running("aider", "fish"));
+
+        // Unknown providers are never running
+        assert!(!is_agent_running("unknown", "node"));
     }
 }
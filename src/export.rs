@@ -0,0 +1,386 @@
+//! Monitor stats export (JSON, CSV, Markdown)
+//!
+//! Used by the `e` key in the monitor and by `coder-tools export`.
+
+use crate::app::App;
+use crate::config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Markdown];
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportData {
+    /// ISO-8601 timestamp with local offset
+    pub timestamp: String,
+    pub summary: ExportSummary,
+    pub panes: Vec<ExportPane>,
+}
+
+#[derive(Serialize)]
+pub struct ExportSummary {
+    pub total_panes: usize,
+    pub total_working_secs: u64,
+    pub total_waiting_secs: u64,
+    pub total_permission_secs: u64,
+    pub total_state_changes: u32,
+    pub efficiency_percent: f64,
+    pub total_tokens: u64,
+    pub total_cost_usd: f64,
+}
+
+#[derive(Serialize)]
+pub struct ExportPane {
+    pub session: String,
+    pub window: u32,
+    pub pane: u32,
+    pub path: String,
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<String>,
+    pub current_status: String,
+    pub task: Option<String>,
+    pub working_secs: u64,
+    pub waiting_secs: u64,
+    pub permission_secs: u64,
+    pub state_changes: u32,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cache_read_tokens: Option<u64>,
+    pub cache_write_tokens: Option<u64>,
+    pub cost_usd: Option<f64>,
+}
+
+/// One-shot export of the current tmux panes (`coder-tools export`)
+pub fn run(formats: Vec<ExportFormat>, dir: Option<PathBuf>, all: bool) -> Result<()> {
+    let config = config::load()?;
+    let mut app = App::new(0, all, false);
+    app.refresh()?;
+    app.refresh_costs();
+
+    let formats = if formats.is_empty() {
+        configured_formats(&config)
+    } else {
+        formats
+    };
+    let dir = dir.unwrap_or_else(|| config.export_dir());
+
+    let now = Local::now();
+    let data = collect(&app, now);
+    for path in write(&data, now, &dir, &formats)? {
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Export from the monitor, returning a toast message for the footer
+pub fn export_from_monitor(app: &App, config: &config::Config) -> Result<String> {
+    let now = Local::now();
+    let data = collect(app, now);
+    let paths = write(&data, now, &config.export_dir(), &configured_formats(config))?;
+
+    let first = paths.first().context("No export formats configured")?;
+    let mut message = format!("Exported to {}", first.display());
+    if paths.len() > 1 {
+        let others: Vec<&str> = paths[1..]
+            .iter()
+            .filter_map(|p| p.extension().and_then(|e| e.to_str()))
+            .collect();
+        message.push_str(&format!(" (+{})", others.join(", ")));
+    }
+    Ok(message)
+}
+
+/// Snapshot the visible panes of the app
+pub fn collect(app: &App, now: DateTime<Local>) -> ExportData {
+    let stats = app.aggregated_stats();
+
+    let panes: Vec<ExportPane> = app
+        .visible_panes()
+        .iter()
+        .map(|p| {
+            let times = p.time_in_states();
+            let tokens = p.tokens.as_ref();
+
+            ExportPane {
+                session: p.pane.session_name.clone(),
+                window: p.pane.window_index,
+                pane: p.pane.pane_index,
+                path: p.pane.current_path.clone(),
+                provider: p.pane.agent_provider.clone(),
+                profile: p.profile(),
+                session_id: p.pane.agent_session_id.clone(),
+                transcript: p.transcript.as_ref().map(|t| t.to_string_lossy().to_string()),
+                current_status: p.status.status.label().to_string(),
+                task: p.status.task.clone(),
+                working_secs: times.total_working_secs,
+                waiting_secs: times.total_waiting_secs,
+                permission_secs: times.total_permission_secs,
+                state_changes: times.state_changes,
+                input_tokens: tokens.map(|t| t.input_tokens),
+                output_tokens: tokens.map(|t| t.output_tokens),
+                cache_read_tokens: tokens.map(|t| t.cache_read_tokens),
+                cache_write_tokens: tokens.map(|t| t.cache_write_tokens),
+                cost_usd: tokens.map(|t| t.cost_usd()),
+            }
+        })
+        .collect();
+
+    let total_tokens = panes
+        .iter()
+        .map(|p| p.input_tokens.unwrap_or(0) + p.output_tokens.unwrap_or(0))
+        .sum();
+    let total_cost_usd = panes.iter().filter_map(|p| p.cost_usd).sum();
+
+    ExportData {
+        timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        summary: ExportSummary {
+            total_panes: stats.pane_count,
+            total_working_secs: stats.total_working_secs,
+            total_waiting_secs: stats.total_waiting_secs,
+            total_permission_secs: stats.total_permission_secs,
+            total_state_changes: stats.total_state_changes,
+            efficiency_percent: stats.efficiency_percent(),
+            total_tokens,
+            total_cost_usd,
+        },
+        panes,
+    }
+}
+
+/// Formats from the config, defaulting to all of them
+pub fn configured_formats(config: &config::Config) -> Vec<ExportFormat> {
+    let formats: Vec<ExportFormat> = config
+        .export_formats
+        .iter()
+        .flatten()
+        .filter_map(|f| ExportFormat::parse(f))
+        .collect();
+    if formats.is_empty() {
+        ExportFormat::ALL.to_vec()
+    } else {
+        formats
+    }
+}
+
+/// Write one file per format into `dir`, returning the written paths
+pub fn write(
+    data: &ExportData,
+    now: DateTime<Local>,
+    dir: &Path,
+    formats: &[ExportFormat],
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create export directory {}", dir.display()))?;
+
+    let stem = format!("coder-tools-stats-{}", now.format("%Y-%m-%dT%H-%M-%S"));
+    let mut written = Vec::new();
+
+    for format in formats {
+        let content = match format {
+            ExportFormat::Json => serde_json::to_string_pretty(data)?,
+            ExportFormat::Csv => to_csv(data),
+            ExportFormat::Markdown => to_markdown(data),
+        };
+        let path = dir.join(format!("{}.{}", stem, format.extension()));
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn to_csv(data: &ExportData) -> String {
+    let mut out = String::from(
+        "timestamp,session,window,pane,path,provider,status,task,working_secs,waiting_secs,permission_secs,state_changes,input_tokens,output_tokens,cache_read_tokens,cache_write_tokens,cost_usd\n",
+    );
+
+    for p in &data.panes {
+        let fields = [
+            data.timestamp.clone(),
+            csv_field(&p.session),
+            p.window.to_string(),
+            p.pane.to_string(),
+            csv_field(&p.path),
+            csv_field(p.provider.as_deref().unwrap_or("")),
+            csv_field(&p.current_status),
+            csv_field(p.task.as_deref().unwrap_or("")),
+            p.working_secs.to_string(),
+            p.waiting_secs.to_string(),
+            p.permission_secs.to_string(),
+            p.state_changes.to_string(),
+            opt(p.input_tokens),
+            opt(p.output_tokens),
+            opt(p.cache_read_tokens),
+            opt(p.cache_write_tokens),
+            opt(p.cost_usd.map(|c| format!("{:.4}", c))),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn to_markdown(data: &ExportData) -> String {
+    use crate::app::format_duration;
+    use crate::cost::{format_cost, format_tokens};
+    use std::time::Duration;
+
+    let s = &data.summary;
+    let mut out = format!("# Agent Monitor Stats\n\nExported {}\n\n", data.timestamp);
+
+    out.push_str("## Summary\n\n");
+    out.push_str(&format!("- Panes: {}\n", s.total_panes));
+    out.push_str(&format!("- Working: {}\n", format_duration(Duration::from_secs(s.total_working_secs))));
+    out.push_str(&format!("- Waiting: {}\n", format_duration(Duration::from_secs(s.total_waiting_secs))));
+    out.push_str(&format!("- Permission: {}\n", format_duration(Duration::from_secs(s.total_permission_secs))));
+    out.push_str(&format!("- State changes: {}\n", s.total_state_changes));
+    out.push_str(&format!("- Efficiency: {:.1}%\n", s.efficiency_percent));
+    out.push_str(&format!("- Tokens: {}\n", format_tokens(s.total_tokens)));
+    out.push_str(&format!("- Cost: {}\n\n", format_cost(s.total_cost_usd)));
+
+    out.push_str("## Panes\n\n");
+    out.push_str("| Pane | Path | Provider | Status | Working | Waiting | Permission | Changes | Tokens | Cost |\n");
+    out.push_str("|------|------|----------|--------|---------|---------|------------|---------|--------|------|\n");
+
+    for p in &data.panes {
+        let tokens = match (p.input_tokens, p.output_tokens) {
+            (Some(i), Some(o)) => format_tokens(i + o),
+            _ => "-".to_string(),
+        };
+        let cost = p.cost_usd.map(format_cost).unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "| {}:{}.{} | {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            md_cell(&p.session),
+            p.window,
+            p.pane,
+            md_cell(&p.path),
+            md_cell(p.provider.as_deref().unwrap_or("-")),
+            p.current_status,
+            format_duration(Duration::from_secs(p.working_secs)),
+            format_duration(Duration::from_secs(p.waiting_secs)),
+            format_duration(Duration::from_secs(p.permission_secs)),
+            p.state_changes,
+            tokens,
+            cost,
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ExportData {
+        ExportData {
+            timestamp: "2026-01-02T03:04:05+00:00".to_string(),
+            summary: ExportSummary {
+                total_panes: 1,
+                total_working_secs: 90,
+                total_waiting_secs: 10,
+                total_permission_secs: 0,
+                total_state_changes: 3,
+                efficiency_percent: 90.0,
+                total_tokens: 1200,
+                total_cost_usd: 0.5,
+            },
+            panes: vec![ExportPane {
+                session: "dev".to_string(),
+                window: 1,
+                pane: 0,
+                path: "/home/user/a,b".to_string(),
+                provider: Some("claude".to_string()),
+                profile: None,
+                session_id: None,
+                transcript: None,
+                current_status: "Working".to_string(),
+                task: Some("say \"hi\"".to_string()),
+                working_secs: 90,
+                waiting_secs: 10,
+                permission_secs: 0,
+                state_changes: 3,
+                input_tokens: Some(1000),
+                output_tokens: Some(200),
+                cache_read_tokens: Some(0),
+                cache_write_tokens: Some(0),
+                cost_usd: Some(0.5),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        let csv = to_csv(&sample());
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("2026-01-02T03:04:05+00:00,dev,1,0,\"/home/user/a,b\",claude,"));
+        assert!(row.contains("\"say \"\"hi\"\"\""));
+        assert!(row.ends_with(",1000,200,0,0,0.5000"));
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let md = to_markdown(&sample());
+        assert!(md.contains("| dev:1.0 | /home/user/a,b | claude | Working | 1m30s | 10s | 0s | 3 | 1.2k | $0.50 |"));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("md"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::parse("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::parse("xml"), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Handler run by agent hooks: `coder-tools hook <event>`
+//!
+//! Agents pass the event as JSON on stdin (Codex appends it as the last
+//! argument instead). The handler publishes the agent's status and session
+//! details as tmux pane options, and to a state file (see `state`) so agents
+//! outside tmux are seen too. A hook must never get in the agent's way, so
+//! failures are ignored and it always exits 0.
+
+use crate::profile;
+use crate::state;
+use crate::mux::tmux;
+use anyhow::Result;
+use clap::Args;
+use serde_json::Value;
+use std::io::{self, IsTerminal, Read};
+use std::process::Command;
+
+/// Longest @agent_task or @agent_tool value, in characters
+const MAX_FIELD_CHARS: usize = 100;
+
+#[derive(Args, Debug)]
+pub struct HookArgs {
+    /// Event that fired (UserPromptSubmit, Stop, BeforeAgent, notify, idle, ...)
+    pub event: String,
+
+    /// Agent that fired it
+    #[arg(long, default_value = "claude")]
+    pub provider: String,
+
+    /// Event JSON, when it isn't given on stdin
+    pub payload: Option<String>,
+
+    /// Program to pass the event on to, given after `--` (the notify program
+    /// Codex had before ours, since it only runs one)
+    #[arg(last = true)]
+    pub chain: Vec<String>,
+}
+
+pub fn run(mut args: HookArgs) -> Result<()> {
+    // Codex appends the event after the chained program's arguments
+    let raw = args.payload.take().or_else(|| args.chain.pop()).or_else(read_stdin);
+    let payload = raw
+        .as_deref()
+        .and_then(|text| serde_json::from_str(text).ok())
+        .unwrap_or(Value::Null);
+
+    let mut options = pane_options(&args.provider, &args.event, &payload);
+    if args.provider == "claude" {
+        if let Some(profile) = claude_profile(&payload) {
+            options.push(("@agent_profile", profile.name.clone()));
+        }
+    }
+    let _ = state::publish(&args.provider, &options);
+    if let Ok(pane_id) = std::env::var("TMUX_PANE") {
+        if !pane_id.is_empty() {
+            let _ = tmux::set_pane_options(&pane_id, &options);
+        }
+    }
+    if let (Some((program, program_args)), Some(raw)) = (args.chain.split_first(), raw) {
+        // Not waited for, as Codex doesn't wait for its notify program either
+        let _ = Command::new(program).args(program_args).arg(raw).spawn();
+    }
+    Ok(())
+}
+
+/// Hook input, unless stdin is a terminal (Aider passes nothing)
+pub fn read_stdin() -> Option<String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+    let mut text = String::new();
+    stdin.read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+/// Profile Claude runs under: where its transcript lives, or else its
+/// CLAUDE_CONFIG_DIR, which hooks inherit
+pub fn claude_profile(payload: &Value) -> Option<&'static profile::Profile> {
+    payload
+        .get("transcript_path")
+        .and_then(|p| p.as_str())
+        .and_then(|p| profile::for_path(std::path::Path::new(p)))
+        .or_else(profile::current)
+}
+
+/// Status an event puts the agent in, if it changes it at all
+fn event_status(event: &str, payload: &Value) -> Option<&'static str> {
+    match event {
+        "UserPromptSubmit" | "PreToolUse" | "BeforeAgent" | "BeforeTool" => Some("working"),
+        "Stop" | "AfterAgent" => Some("waiting"),
+        "PermissionRequest" => Some("permission"),
+        // Codex only reports finished turns, so its panes never show working
+        "notify" => match payload.get("type").and_then(|t| t.as_str()) {
+            Some("agent-turn-complete") => Some("waiting"),
+            _ => Some("working"),
+        },
+        // Aider runs its notifications command when it wants input
+        "idle" => Some("waiting"),
+        _ => None,
+    }
+}
+
+/// Pane options to set for an event
+///
+/// Fields missing from the payload keep their previous values, except the
+/// tool, which only describes the event that set it.
+fn pane_options(provider: &str, event: &str, payload: &Value) -> Vec<(&'static str, String)> {
+    let text = |key: &str| {
+        payload
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut options = vec![("@agent_provider", provider.to_string())];
+    if let Some(status) = event_status(event, payload) {
+        options.push(("@agent_status", status.to_string()));
+    }
+    if let Some(task) = prompt(payload) {
+        options.push(("@agent_task", truncate(&task, MAX_FIELD_CHARS)));
+    }
+    // Codex uses kebab-case keys
+    if let Some(id) = text("session_id").or_else(|| text("thread-id")) {
+        options.push(("@agent_session_id", id));
+    }
+    if let Some(path) = text("transcript_path") {
+        options.push(("@agent_transcript", path));
+    }
+    if let Some(cwd) = text("cwd") {
+        options.push(("@agent_cwd", cwd));
+    }
+    let tool = tool_summary(payload).map(|t| truncate(&t, MAX_FIELD_CHARS));
+    options.push(("@agent_tool", tool.unwrap_or_default()));
+
+    options
+}
+
+/// The user's prompt: Claude and Gemini send `prompt`, Codex the turn's inputs
+fn prompt(payload: &Value) -> Option<String> {
+    payload
+        .get("prompt")
+        .and_then(|p| p.as_str())
+        .or_else(|| {
+            payload
+                .get("input-messages")
+                .and_then(|m| m.as_array())
+                .and_then(|m| m.last())
+                .and_then(|m| m.as_str())
+        })
+        .filter(|p| !p.trim().is_empty())
+        .map(str::to_string)
+}
+
+/// "Bash: cargo test" from `tool_name` and the most telling `tool_input` field
+fn tool_summary(payload: &Value) -> Option<String> {
+    let name = payload.get("tool_name").and_then(|n| n.as_str())?;
+    let detail = payload.get("tool_input").and_then(|input| {
+        ["command", "file_path", "path", "pattern", "url", "description"]
+            .iter()
+            .find_map(|key| input.get(*key).and_then(|v| v.as_str()))
+    });
+
+    Some(match detail {
+        Some(detail) => format!("{}: {}", name, detail),
+        None => name.to_string(),
+    })
+}
+
+/// Collapse whitespace onto one line and cut at a character boundary
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(max_chars)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn option<'a>(options: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+        options.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_claude_prompt_options() {
+        let prompt = format!("fix\nthe {}", "ü".repeat(200));
+        let payload = json!({
+            "hook_event_name": "UserPromptSubmit",
+            "session_id": "abc-123",
+            "transcript_path": "/home/u/.claude/projects/-p/abc-123.jsonl",
+            "cwd": "/home/u/p",
+            "prompt": prompt,
+        });
+        let options = pane_options("claude", "UserPromptSubmit", &payload);
+
+        assert_eq!(option(&options, "@agent_status"), Some("working"));
+        assert_eq!(option(&options, "@agent_session_id"), Some("abc-123"));
+        assert_eq!(option(&options, "@agent_cwd"), Some("/home/u/p"));
+        assert_eq!(option(&options, "@agent_tool"), Some(""));
+
+        // Counted in characters, so multi-byte text is never split
+        let task = option(&options, "@agent_task").unwrap();
+        assert!(task.starts_with("fix the üü"));
+        assert_eq!(task.chars().count(), MAX_FIELD_CHARS);
+
+        let payload = json!({"tool_name": "Bash", "tool_input": {"command": "cargo test"}});
+        let options = pane_options("claude", "PermissionRequest", &payload);
+        assert_eq!(option(&options, "@agent_status"), Some("permission"));
+        assert_eq!(option(&options, "@agent_tool"), Some("Bash: cargo test"));
+        assert_eq!(option(&options, "@agent_task"), None);
+    }
+
+    #[test]
+    fn test_codex_notify_options() {
+        let payload = json!({
+            "type": "agent-turn-complete",
+            "thread-id": "0199-abc",
+            "cwd": "/home/u/p",
+            "input-messages": ["first", "rename the module"],
+        });
+        let options = pane_options("codex", "notify", &payload);
+
+        assert_eq!(option(&options, "@agent_provider"), Some("codex"));
+        assert_eq!(option(&options, "@agent_status"), Some("waiting"));
+        assert_eq!(option(&options, "@agent_session_id"), Some("0199-abc"));
+        assert_eq!(option(&options, "@agent_task"), Some("rename the module"));
+
+        let options = pane_options("aider", "idle", &Value::Null);
+        assert_eq!(option(&options, "@agent_status"), Some("waiting"));
+    }
+}
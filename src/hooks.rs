@@ -1,197 +1,1105 @@
+//! Status hooks in each agent's own settings
+//!
+//! Agents report their status through `coder-tools hook <event>`, which
+//! coder-tools registers in their settings files. Every change is shown as a diff
+//! and written with a backup; only entries coder-tools owns are ever removed.
+
+use crate::profile;
+use crate::providers::{self, Provider};
 use anyhow::{Context, Result};
+use clap::Subcommand;
 use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum HooksAction {
+    /// Show which events are wired for each agent
+    Status,
+
+    /// Preview the changes `install` would make
+    Diff {
+        /// Only this agent (claude, gemini, codex, aider)
+        provider: Option<String>,
+    },
+
+    /// Add coder-tools hooks, after showing the changes
+    Install {
+        /// Only this agent (claude, gemini, codex, aider)
+        provider: Option<String>,
+
+        /// Apply without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove coder-tools hooks, leaving all other entries in place
+    Uninstall {
+        /// Only this agent (claude, gemini, codex, aider)
+        provider: Option<String>,
+
+        /// Apply without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+pub fn run(action: HooksAction) -> Result<()> {
+    match action {
+        HooksAction::Status => show_status(),
+        HooksAction::Diff { provider } => {
+            let changes = plan_changes(provider.as_deref(), Plan::Install)?;
+            if changes.is_empty() {
+                println!("Hooks are up to date.");
+            }
+            for change in &changes {
+                print_diff(change);
+            }
+            Ok(())
+        }
+        HooksAction::Install { provider, yes } => apply_with_consent(provider.as_deref(), Plan::Install, yes),
+        HooksAction::Uninstall { provider, yes } => apply_with_consent(provider.as_deref(), Plan::Uninstall, yes),
+    }
+}
+
+/// How an event is wired in an agent's settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wiring {
+    /// Our hook is present and current
+    Installed,
+    /// Our hook from an older version, upgraded by `install`
+    Outdated,
+    /// Only hooks of the user's own
+    Other,
+    Missing,
+}
+
+/// An agent settings file that coder-tools can wire status hooks into
+pub trait HookConfig: Sync {
+    /// Settings files to wire, one per profile where the agent has several
+    fn paths(&self) -> Result<Vec<PathBuf>>;
+
+    /// Whether to touch this file at all; agents that aren't installed are skipped
+    fn agent_present(&self, _path: &Path) -> bool {
+        true
+    }
+
+    /// Settings content with our hooks added; unchanged if already installed
+    fn install(&self, content: &str) -> Result<String>;
+
+    /// Settings content with our hooks removed
+    fn uninstall(&self, content: &str) -> Result<String>;
+
+    /// Wiring of each event we rely on
+    fn events(&self, content: &str) -> Vec<(&'static str, Wiring)>;
+
+    /// What the agent's hooks can't report, shown by `hooks status`
+    fn note(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Plan {
+    Install,
+    Uninstall,
+}
+
+/// A pending rewrite of one settings file
+struct Change {
+    provider: &'static dyn Provider,
+    path: PathBuf,
+    before: String,
+    after: String,
+}
+
+impl Change {
+    fn plan(
+        provider: &'static dyn Provider,
+        config: &dyn HookConfig,
+        path: PathBuf,
+        plan: Plan,
+    ) -> Result<Option<Change>> {
+        if !config.agent_present(&path) {
+            return Ok(None);
+        }
+
+        let before = if path.exists() {
+            fs::read_to_string(&path)?
+        } else if plan == Plan::Uninstall {
+            return Ok(None);
+        } else {
+            String::new()
+        };
+
+        let after = match plan {
+            Plan::Install => config.install(&before)?,
+            Plan::Uninstall => config.uninstall(&before)?,
+        };
+        if after == before {
+            return Ok(None);
+        }
+
+        Ok(Some(Change {
+            provider,
+            path,
+            before,
+            after,
+        }))
+    }
 
-/// Ensure hooks are installed for all supported providers.
-/// This is called before the TUI starts to auto-inject hooks if missing.
-pub fn ensure_hooks_installed() -> Result<()> {
-    // Check and inject Claude hooks
-    if let Err(e) = check_and_inject_claude_hooks() {
-        eprintln!("Warning: Could not set up Claude hooks: {}", e);
+    fn apply(&self) -> Result<()> {
+        if self.path.exists() {
+            create_backup(&self.path)?;
+        } else if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, &self.after)?;
+        Ok(())
     }
+}
+
+/// Install missing hooks for every agent, as the monitor does on start
+///
+/// Returns the names of agents whose settings were changed.
+pub fn ensure_hooks_installed() -> Vec<&'static str> {
+    let mut installed = Vec::new();
 
-    // Check and inject Gemini hooks
-    if let Err(e) = check_and_inject_gemini_hooks() {
-        eprintln!("Warning: Could not set up Gemini hooks: {}", e);
+    for provider in providers::all() {
+        let config = match provider.hooks() {
+            Some(c) => c,
+            None => continue,
+        };
+        let paths = match config.paths() {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("Warning: Could not set up {} hooks: {}", provider.display_name(), e);
+                continue;
+            }
+        };
+        for path in paths {
+            let result = Change::plan(*provider, config, path, Plan::Install)
+                .and_then(|change| change.map(|c| c.apply()).transpose());
+            match result {
+                Ok(Some(())) if !installed.contains(&provider.display_name()) => {
+                    installed.push(provider.display_name())
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Warning: Could not set up {} hooks: {}", provider.display_name(), e),
+            }
+        }
     }
 
+    installed
+}
+
+fn selected_providers(name: Option<&str>) -> Result<Vec<(&'static dyn Provider, &'static dyn HookConfig)>> {
+    let selected: Vec<_> = providers::all()
+        .iter()
+        .filter(|p| name.map(|n| p.name() == n).unwrap_or(true))
+        .filter_map(|p| p.hooks().map(|config| (*p, config)))
+        .collect();
+
+    if let (Some(name), true) = (name, selected.is_empty()) {
+        let known: Vec<_> = providers::all()
+            .iter()
+            .filter(|p| p.hooks().is_some())
+            .map(|p| p.name())
+            .collect();
+        anyhow::bail!("No hooks for '{}' (known: {})", name, known.join(", "));
+    }
+    Ok(selected)
+}
+
+fn plan_changes(name: Option<&str>, plan: Plan) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    for (provider, config) in selected_providers(name)? {
+        for path in config.paths()? {
+            if let Some(change) = Change::plan(provider, config, path, plan)? {
+                changes.push(change);
+            }
+        }
+    }
+    Ok(changes)
+}
+
+fn apply_with_consent(name: Option<&str>, plan: Plan, yes: bool) -> Result<()> {
+    confirm_and_apply(&plan_changes(name, plan)?, yes)?;
     Ok(())
 }
 
-fn get_claude_settings_path() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".claude").join("settings.json"))
+/// Show the diffs, then write them once the user agrees
+///
+/// Returns false if the user declined.
+fn confirm_and_apply(changes: &[Change], yes: bool) -> Result<bool> {
+    if changes.is_empty() {
+        println!("Nothing to change.");
+        return Ok(true);
+    }
+
+    for change in changes {
+        print_diff(change);
+    }
+
+    if !yes && !confirm("Apply these changes?")? {
+        println!("No changes made.");
+        return Ok(false);
+    }
+
+    for change in changes {
+        change.apply()?;
+        println!("Updated {} ({})", change.path.display(), change.provider.display_name());
+    }
+    Ok(true)
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
 }
 
-fn get_gemini_settings_path() -> Result<PathBuf> {
+fn print_diff(change: &Change) {
+    println!("--- {} ({})", change.path.display(), change.provider.display_name());
+    println!("+++ {}", change.path.display());
+
+    let diff = similar::TextDiff::from_lines(&change.before, &change.after);
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        print!("{}", hunk);
+    }
+    println!();
+}
+
+fn show_status() -> Result<()> {
+    for (provider, config) in selected_providers(None)? {
+        for path in config.paths()? {
+            println!("{}  {}", provider.display_name(), path.display());
+
+            if !config.agent_present(&path) {
+                println!("  not installed");
+                println!();
+                continue;
+            }
+
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            for (event, wiring) in config.events(&content) {
+                let (icon, label) = match wiring {
+                    Wiring::Installed => ("✓", "coder-tools"),
+                    Wiring::Outdated => ("↑", "coder-tools (outdated)"),
+                    Wiring::Other => ("~", "other hooks only"),
+                    Wiring::Missing => ("✗", "not wired"),
+                };
+                println!("  {} {:<22} {}", icon, event, label);
+            }
+            if let Some(note) = config.note() {
+                println!("  note: {}", note);
+            }
+            println!();
+        }
+    }
+
+    println!("Use 'coder-tools hooks install' to wire missing events and upgrade outdated ones");
+    Ok(())
+}
+
+fn home_path(relative: &str) -> Result<PathBuf> {
     let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".gemini").join("settings.json"))
+    Ok(PathBuf::from(home).join(relative))
 }
 
-fn create_backup(path: &PathBuf) -> Result<()> {
-    let backup_path = path.with_extension("json.bak");
+fn create_backup(path: &Path) -> Result<()> {
+    let mut backup_name = path.file_name().context("Invalid settings path")?.to_os_string();
+    backup_name.push(".bak");
+    let backup_path = path.with_file_name(backup_name);
     fs::copy(path, &backup_path).context("Failed to create backup")?;
     Ok(())
 }
 
-fn claude_hooks() -> Value {
-    json!({
-        "UserPromptSubmit": [{
-            "hooks": [{
-                "type": "command",
-                "command": "bash -c 'TASK=$(jq -r \".prompt // empty\" | tr \"\\n\" \" \" | head -c 100); tmux set -p @agent_provider claude \\; set -p @agent_task \"$TASK\" \\; set -p @agent_status working 2>/dev/null'"
-            }]
-        }],
-        "Stop": [{
-            "hooks": [{
-                "type": "command",
-                "command": "tmux set -p @agent_status waiting 2>/dev/null || true"
-            }]
-        }],
-        "PermissionRequest": [{
-            "hooks": [{
-                "type": "command",
-                "command": "tmux set -p @agent_status permission 2>/dev/null || true"
-            }]
-        }]
-    })
-}
-
-fn gemini_hooks() -> Value {
-    json!({
-        "BeforeAgent": [{
-            "hooks": [{
-                "type": "command",
-                "command": "tmux set -p @agent_provider gemini \\; set -p @agent_status working 2>/dev/null"
-            }]
-        }],
-        "AfterAgent": [{
-            "hooks": [{
-                "type": "command",
-                "command": "tmux set -p @agent_status waiting 2>/dev/null"
-            }]
+/// Tag on every hook entry written by coder-tools: a trailing shell comment,
+/// or the `$0` of the Codex notify program
+const HOOK_MARKER: &str = "coder-tools:status-hook";
+
+/// Commands of versions from before entries were tagged, exactly as they
+/// were written
+const LEGACY_COMMANDS: &[&str] = &[
+    r#"bash -c 'TASK=$(jq -r ".prompt // empty" | tr "\n" " " | head -c 100); tmux set -p @agent_provider claude \; set -p @agent_task "$TASK" \; set -p @agent_status working 2>/dev/null'"#,
+    r#"bash -c 'INPUT=$(cat); TASK=$(printf "%s" "$INPUT" | jq -r ".prompt // empty" | tr "\n" " " | head -c 100); SID=$(printf "%s" "$INPUT" | jq -r ".session_id // empty"); TRANSCRIPT=$(printf "%s" "$INPUT" | jq -r ".transcript_path // empty"); tmux set -p @agent_provider claude \; set -p @agent_task "$TASK" \; set -p @agent_session_id "$SID" \; set -p @agent_transcript "$TRANSCRIPT" \; set -p @agent_status working 2>/dev/null'"#,
+    "tmux set -p @agent_status waiting 2>/dev/null || true",
+    "tmux set -p @agent_status permission 2>/dev/null || true",
+    r#"tmux set -p @agent_provider gemini \; set -p @agent_status working 2>/dev/null"#,
+    r#"bash -c 'INPUT=$(cat); SID=$(printf "%s" "$INPUT" | jq -r ".session_id // empty"); TRANSCRIPT=$(printf "%s" "$INPUT" | jq -r ".transcript_path // empty"); tmux set -p @agent_provider gemini \; set -p @agent_session_id "$SID" \; set -p @agent_transcript "$TRANSCRIPT" \; set -p @agent_status working 2>/dev/null'"#,
+    "tmux set -p @agent_status waiting 2>/dev/null",
+    r#"tmux set -p @agent_provider aider \; set -p @agent_status waiting 2>/dev/null || true"#,
+];
+
+/// `$0` of the Codex notify script from before entries were tagged
+const LEGACY_NOTIFY_NAME: &str = "coder-tools-notify";
+
+/// Whether a hook command was written by coder-tools: tagged, or exactly
+/// what an older version wrote. A user's own command that merely sets the
+/// same pane options is not ours.
+fn is_ours(command: &str) -> bool {
+    command.contains(HOOK_MARKER) || LEGACY_COMMANDS.contains(&command)
+}
+
+/// Shell command tagged as ours
+fn tagged(command: &str) -> String {
+    format!("{} # {}", command, HOOK_MARKER)
+}
+
+/// Name hook commands invoke coder-tools by
+const PROGRAM: &str = "coder-tools";
+
+/// How hook commands should invoke coder-tools: by name when it is on PATH,
+/// so every install writes the same command, else as this executable
+fn hook_program() -> String {
+    let on_path = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(PROGRAM).is_file()))
+        .unwrap_or(false);
+    if on_path {
+        return PROGRAM.to_string();
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.to_str().map(str::to_string))
+        .unwrap_or_else(|| PROGRAM.to_string())
+}
+
+/// Whether a hook's program can still be run: a name looked up on PATH, or
+/// a path that exists
+fn runnable(program: &str) -> bool {
+    !program.contains('/') || Path::new(program).exists()
+}
+
+/// A command's program, unquoted, and the rest of it, as `shell_quote`
+/// wrote them
+fn split_program(command: &str) -> Option<(String, &str)> {
+    let quoted = match command.strip_prefix('\'') {
+        Some(quoted) => quoted,
+        None => {
+            let end = command.find(' ')?;
+            return Some((command[..end].to_string(), &command[end..]));
+        }
+    };
+    let mut program = String::new();
+    let mut rest = quoted;
+    loop {
+        let end = rest.find('\'')?;
+        program.push_str(&rest[..end]);
+        rest = &rest[end + 1..];
+        // A quote inside the program is written as '\''
+        match rest.strip_prefix("\\''") {
+            Some(after) => {
+                program.push('\'');
+                rest = after;
+            }
+            None => return Some((program, rest)),
+        }
+    }
+}
+
+/// Whether an existing command does what `desired` does
+///
+/// Another install of coder-tools (a dev build, an older location) writes
+/// its own path; its entries count as current as long as that program is
+/// still there, so two installs don't keep rewriting each other's.
+fn same_hook(existing: &str, desired: &str) -> bool {
+    if existing == desired {
+        return true;
+    }
+    match (split_program(existing), split_program(desired)) {
+        (Some((program, args)), Some((_, desired_args))) => args == desired_args && runnable(&program),
+        _ => false,
+    }
+}
+
+/// Quote for POSIX shells, unless nothing needs quoting
+fn shell_quote(text: &str) -> String {
+    let plain = text
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-+:@".contains(c));
+    if plain && !text.is_empty() {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r#"'\''"#))
+    }
+}
+
+/// Shell command running our hook handler, tagged as ours
+fn hook_command(args: &str) -> String {
+    tagged(&format!("{} hook {}", shell_quote(&hook_program()), args))
+}
+
+/// Claude Code hook commands by event
+fn claude_hooks() -> Vec<(&'static str, String)> {
+    ["UserPromptSubmit", "PreToolUse", "PermissionRequest", "Stop"]
+        .into_iter()
+        .map(|event| (event, hook_command(event)))
+        .collect()
+}
+
+/// Gemini CLI hook commands by event
+fn gemini_hooks() -> Vec<(&'static str, String)> {
+    ["BeforeAgent", "BeforeTool", "AfterAgent"]
+        .into_iter()
+        .map(|event| (event, hook_command(&format!("{} --provider gemini", event))))
+        .collect()
+}
+
+/// Tag on the budget hook's entries, which the status hooks leave alone
+const BUDGET_MARKER: &str = "coder-tools:budget-hook";
+
+fn is_budget_hook(command: &str) -> bool {
+    command.contains(BUDGET_MARKER)
+}
+
+/// Claude Code budget hook commands for `events`
+fn budget_hooks(events: &[&'static str]) -> Vec<(&'static str, String)> {
+    events
+        .iter()
+        .map(|event| {
+            let command = format!("{} budget check {}", shell_quote(&hook_program()), event);
+            (*event, format!("{} # {}", command, BUDGET_MARKER))
+        })
+        .collect()
+}
+
+/// Settings content with the budget hook wired to exactly `events`
+fn wire_budget_events(content: &str, events: &[&'static str]) -> Result<String> {
+    let original = parse_settings(content)?;
+    let mut settings = original.clone();
+    remove_json_hooks(&mut settings, is_budget_hook);
+    if !events.is_empty() {
+        merge_json_hooks(&mut settings, &budget_hooks(events), is_budget_hook)?;
+    }
+    settings_text(content, &original, &settings)
+}
+
+/// Wire `coder-tools budget check` to `events` in a Claude Code settings
+/// file, removing it from all others, after showing the change
+///
+/// Returns false if the user declined.
+pub fn wire_budget_hooks(path: &Path, events: &[&'static str], yes: bool) -> Result<bool> {
+    let before = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+    let after = wire_budget_events(&before, events)?;
+    let provider = providers::get("claude").context("Claude Code provider missing")?;
+    let changes = if after == before {
+        Vec::new()
+    } else {
+        vec![Change {
+            provider,
+            path: path.to_path_buf(),
+            before,
+            after,
         }]
-    })
+    };
+    confirm_and_apply(&changes, yes)
+}
+
+fn hook_entry(command: &str) -> Value {
+    json!({ "type": "command", "command": command })
+}
+
+/// Whether a hook entry's command is one `owns` claims
+fn entry_is_owned(entry: &Value, owns: fn(&str) -> bool) -> bool {
+    entry.get("command").and_then(|c| c.as_str()).map(owns).unwrap_or(false)
+}
+
+/// Keep only groups that still have hook entries
+fn drop_empty_groups(groups: &mut Vec<Value>) {
+    groups.retain(|group| {
+        group.get("hooks").and_then(|h| h.as_array()).map(|h| !h.is_empty()).unwrap_or(true)
+    });
 }
 
-fn has_claude_hooks(settings: &Value) -> bool {
-    let hooks = match settings.get("hooks") {
+fn parse_settings(content: &str) -> Result<Value> {
+    if content.trim().is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_str(content).context("Invalid settings JSON")
+}
+
+/// Settings text for `settings`, keeping `content` as is when nothing changed
+fn settings_text(content: &str, original: &Value, settings: &Value) -> Result<String> {
+    if settings == original && !content.trim().is_empty() {
+        return Ok(content.to_string());
+    }
+    Ok(serde_json::to_string_pretty(settings)? + "\n")
+}
+
+/// Wire each event to our command next to any hooks the user has
+///
+/// An existing entry of ours is rewritten in place (upgrading older
+/// versions) and duplicates are dropped; otherwise a new group is appended
+/// to the event's array.
+fn merge_json_hooks(settings: &mut Value, hooks: &[(&'static str, String)], owns: fn(&str) -> bool) -> Result<()> {
+    if settings.get("hooks").is_none() {
+        settings["hooks"] = json!({});
+    }
+    let hooks_obj = settings["hooks"].as_object_mut().context("hooks is not an object")?;
+
+    for (event, command) in hooks {
+        let desired = hook_entry(command);
+        let groups = hooks_obj
+            .entry(event.to_string())
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .with_context(|| format!("hooks.{} is not an array", event))?;
+
+        let mut found = false;
+        for group in groups.iter_mut() {
+            if let Some(entries) = group.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                entries.retain_mut(|entry| {
+                    if !entry_is_owned(entry, owns) {
+                        return true;
+                    }
+                    if found {
+                        return false;
+                    }
+                    found = true;
+                    let current = entry
+                        .get("command")
+                        .and_then(|c| c.as_str())
+                        .map(|c| same_hook(c, command))
+                        .unwrap_or(false);
+                    if !current {
+                        *entry = desired.clone();
+                    }
+                    true
+                });
+            }
+        }
+        drop_empty_groups(groups);
+
+        if !found {
+            groups.push(json!({ "hooks": [desired] }));
+        }
+    }
+    Ok(())
+}
+
+/// Drop hook commands `owns` claims, then any groups, events and `hooks`
+/// left empty
+fn remove_json_hooks(settings: &mut Value, owns: fn(&str) -> bool) {
+    let hooks_obj = match settings.get_mut("hooks").and_then(|h| h.as_object_mut()) {
         Some(h) => h,
-        None => return false,
+        None => return,
     };
 
-    // Check for required hook types
-    let required = ["UserPromptSubmit", "Stop", "PermissionRequest"];
-    required.iter().all(|key| hooks.get(key).is_some())
+    for groups in hooks_obj.values_mut() {
+        if let Some(groups) = groups.as_array_mut() {
+            for group in groups.iter_mut() {
+                if let Some(entries) = group.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                    entries.retain(|entry| !entry_is_owned(entry, owns));
+                }
+            }
+            drop_empty_groups(groups);
+        }
+    }
+    hooks_obj.retain(|_, groups| groups.as_array().map(|g| !g.is_empty()).unwrap_or(true));
+
+    if hooks_obj.is_empty() {
+        if let Some(obj) = settings.as_object_mut() {
+            obj.remove("hooks");
+        }
+    }
 }
 
-fn has_gemini_hooks(settings: &Value) -> bool {
-    let hooks = match settings.get("hooks") {
-        Some(h) => h,
-        None => return false,
+fn json_event_wiring(settings: &Value, event: &str, desired: &str) -> Wiring {
+    let commands: Vec<&str> = settings
+        .get("hooks")
+        .and_then(|h| h.get(event))
+        .and_then(|groups| groups.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|group| group.get("hooks").and_then(|h| h.as_array()))
+        .flatten()
+        .filter_map(|entry| entry.get("command").and_then(|c| c.as_str()))
+        .collect();
+
+    if commands.iter().any(|c| same_hook(c, desired)) {
+        Wiring::Installed
+    } else if commands.iter().any(|c| is_ours(c)) {
+        Wiring::Outdated
+    } else if commands.is_empty() {
+        Wiring::Missing
+    } else {
+        Wiring::Other
+    }
+}
+
+fn json_events(content: &str, hooks: &[(&'static str, String)]) -> Vec<(&'static str, Wiring)> {
+    let settings = parse_settings(content).unwrap_or_default();
+    hooks
+        .iter()
+        .map(|(event, command)| (*event, json_event_wiring(&settings, event, command)))
+        .collect()
+}
+
+/// Claude Code: settings.json in each profile's config dir (~/.claude)
+pub struct ClaudeSettings;
+
+impl HookConfig for ClaudeSettings {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(profile::active().iter().map(|p| p.settings_path()).collect())
+    }
+
+    fn install(&self, content: &str) -> Result<String> {
+        let original = parse_settings(content)?;
+        let mut settings = original.clone();
+        merge_json_hooks(&mut settings, &claude_hooks(), is_ours)?;
+        settings_text(content, &original, &settings)
+    }
+
+    fn uninstall(&self, content: &str) -> Result<String> {
+        let original = parse_settings(content)?;
+        let mut settings = original.clone();
+        remove_json_hooks(&mut settings, is_ours);
+        settings_text(content, &original, &settings)
+    }
+
+    fn events(&self, content: &str) -> Vec<(&'static str, Wiring)> {
+        json_events(content, &claude_hooks())
+    }
+}
+
+/// Gemini CLI: ~/.gemini/settings.json (hooks need experiments.enableHooks)
+pub struct GeminiSettings;
+
+impl HookConfig for GeminiSettings {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(vec![home_path(".gemini/settings.json")?])
+    }
+
+    fn install(&self, content: &str) -> Result<String> {
+        let original = parse_settings(content)?;
+        let mut settings = original.clone();
+
+        // Ensure experiments.enableHooks is set
+        if settings.get("experiments").is_none() {
+            settings["experiments"] = json!({});
+        }
+        settings["experiments"]["enableHooks"] = json!(true);
+
+        merge_json_hooks(&mut settings, &gemini_hooks(), is_ours)?;
+        settings_text(content, &original, &settings)
+    }
+
+    fn uninstall(&self, content: &str) -> Result<String> {
+        // experiments.enableHooks stays, other hooks may rely on it
+        let original = parse_settings(content)?;
+        let mut settings = original.clone();
+        remove_json_hooks(&mut settings, is_ours);
+        settings_text(content, &original, &settings)
+    }
+
+    fn events(&self, content: &str) -> Vec<(&'static str, Wiring)> {
+        json_events(content, &gemini_hooks())
+    }
+}
+
+/// Codex `notify` program: runs with the event JSON as its last argument,
+/// and passes it on to `chain`, the notify program the user had before
+fn codex_notify(chain: &[String]) -> Vec<String> {
+    let mut args = vec![
+        hook_program(),
+        "hook".to_string(),
+        "notify".to_string(),
+        "--provider".to_string(),
+        "codex".to_string(),
+    ];
+    if !chain.is_empty() {
+        args.push("--".to_string());
+        args.extend_from_slice(chain);
+    }
+    args
+}
+
+/// Whether a notify argv runs our handler or an older script of ours
+fn is_our_notify(args: &[&str]) -> bool {
+    let runs_handler = args.len() >= 3
+        && args[0].rsplit('/').next() == Some("coder-tools")
+        && args[1..3] == ["hook", "notify"];
+    runs_handler || args.last() == Some(&LEGACY_NOTIFY_NAME) || args.iter().any(|a| a.contains(HOOK_MARKER))
+}
+
+/// The user's notify program that ours passes events on to, if any
+fn chained_notify(args: &[&str]) -> Vec<String> {
+    args.iter()
+        .position(|arg| *arg == "--")
+        .map(|i| args[i + 1..].iter().map(|arg| arg.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn parse_codex_config(content: &str) -> Result<toml_edit::DocumentMut> {
+    content.parse().context("Invalid Codex config.toml")
+}
+
+/// Arguments of the `notify` program, empty if it isn't a list of strings
+fn notify_args(doc: &toml_edit::DocumentMut) -> Vec<&str> {
+    doc.get("notify")
+        .and_then(|notify| notify.as_array())
+        .map(|args| args.iter().filter_map(|arg| arg.as_str()).collect())
+        .unwrap_or_default()
+}
+
+fn codex_notify_wiring(doc: &toml_edit::DocumentMut) -> Wiring {
+    if doc.get("notify").is_none() {
+        return Wiring::Missing;
+    }
+    let args = notify_args(doc);
+    let desired = codex_notify(&chained_notify(&args));
+    let current = args.len() == desired.len()
+        && args[1..] == desired[1..]
+        && (args[0] == desired[0] || runnable(args[0]));
+    if current {
+        Wiring::Installed
+    } else if is_our_notify(&args) {
+        Wiring::Outdated
+    } else {
+        Wiring::Other
+    }
+}
+
+fn set_notify(doc: &mut toml_edit::DocumentMut, args: Vec<String>) {
+    doc["notify"] = toml_edit::value(toml_edit::Array::from_iter(args));
+}
+
+/// Add or upgrade the notify program in a Codex config, keeping comments
+/// and layout
+///
+/// Returns `None` when our current entry is already there. Codex only runs
+/// one notify program, so one set by the user is called from ours.
+fn merge_codex_notify(content: &str) -> Result<Option<String>> {
+    let mut doc = parse_codex_config(content)?;
+
+    let chain = match codex_notify_wiring(&doc) {
+        Wiring::Installed => return Ok(None),
+        Wiring::Other => {
+            let args = notify_args(&doc);
+            if args.is_empty() {
+                anyhow::bail!("notify in config.toml is not a list of arguments; leaving it unchanged");
+            }
+            args.iter().map(|arg| arg.to_string()).collect()
+        }
+        Wiring::Outdated => chained_notify(&notify_args(&doc)),
+        Wiring::Missing => Vec::new(),
     };
+    set_notify(&mut doc, codex_notify(&chain));
 
-    // Check for required hook types
-    let required = ["BeforeAgent", "AfterAgent"];
-    required.iter().all(|key| hooks.get(key).is_some())
+    Ok(Some(doc.to_string()))
 }
 
-fn check_and_inject_claude_hooks() -> Result<bool> {
-    let path = get_claude_settings_path()?;
+/// Codex CLI: `notify` in ~/.codex/config.toml
+pub struct CodexConfig;
 
-    // If file doesn't exist, create it with hooks
-    if !path.exists() {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+impl HookConfig for CodexConfig {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(vec![home_path(".codex/config.toml")?])
+    }
+
+    fn agent_present(&self, path: &Path) -> bool {
+        path.parent().map(|dir| dir.exists()).unwrap_or(false)
+    }
+
+    fn install(&self, content: &str) -> Result<String> {
+        Ok(merge_codex_notify(content)?.unwrap_or_else(|| content.to_string()))
+    }
+
+    fn uninstall(&self, content: &str) -> Result<String> {
+        let mut doc = parse_codex_config(content)?;
+        if !matches!(codex_notify_wiring(&doc), Wiring::Installed | Wiring::Outdated) {
+            return Ok(content.to_string());
         }
-        let settings = json!({
-            "hooks": claude_hooks()
-        });
-        fs::write(&path, serde_json::to_string_pretty(&settings)?)?;
-        return Ok(true);
+        // Give the user's own notify program its place back
+        let chain = chained_notify(&notify_args(&doc));
+        if chain.is_empty() {
+            doc.remove("notify");
+        } else {
+            set_notify(&mut doc, chain);
+        }
+        Ok(doc.to_string())
     }
 
-    // Read existing settings
-    let content = fs::read_to_string(&path)?;
-    let mut settings: Value = serde_json::from_str(&content)?;
+    fn events(&self, content: &str) -> Vec<(&'static str, Wiring)> {
+        let wiring = parse_codex_config(content)
+            .map(|doc| codex_notify_wiring(&doc))
+            .unwrap_or(Wiring::Missing);
+        vec![("notify", wiring)]
+    }
 
-    // Check if hooks already exist
-    if has_claude_hooks(&settings) {
-        return Ok(false);
+    fn note(&self) -> Option<&'static str> {
+        Some("Codex only notifies when a turn ends, so its panes show waiting but never working")
     }
+}
+
+/// Aider runs this through the shell whenever it is waiting for input
+fn aider_command() -> String {
+    hook_command("idle --provider aider")
+}
 
-    // Create backup before modifying
-    create_backup(&path)?;
+fn aider_notifications_line() -> String {
+    // YAML single quotes escape a quote by doubling it
+    format!("notifications-command: '{}'", aider_command().replace('\'', "''"))
+}
 
-    // Merge hooks into existing settings
-    if settings.get("hooks").is_none() {
-        settings["hooks"] = json!({});
+/// A YAML scalar as the shell command it holds
+fn yaml_command(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        Some(quoted) => quoted.replace("''", "'"),
+        None => value.to_string(),
     }
+}
 
-    let hooks_obj = settings["hooks"].as_object_mut().context("hooks is not an object")?;
-    let new_hooks = claude_hooks();
-    let new_hooks_obj = new_hooks.as_object().unwrap();
+/// Value of a top-level `key:` line in a YAML config
+fn yaml_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key).and_then(|rest| rest.strip_prefix(':')))
+}
+
+/// Add or upgrade the notifications command in an Aider YAML config
+///
+/// Returns `None` when our current command is already there. A
+/// notifications command set by the user is left alone.
+fn merge_aider_notifications(content: &str) -> Result<Option<String>> {
+    let desired = aider_notifications_line();
 
-    for (key, value) in new_hooks_obj {
-        if !hooks_obj.contains_key(key) {
-            hooks_obj.insert(key.clone(), value.clone());
+    if let Some(existing) = yaml_value(content, "notifications-command").map(yaml_command) {
+        if !is_ours(&existing) {
+            anyhow::bail!("notifications-command is already set in .aider.conf.yml; leaving it unchanged");
         }
+        if same_hook(&existing, &aider_command()) {
+            return Ok(None);
+        }
+        // Rewrite our older line where it stands
+        let mut merged = content
+            .lines()
+            .map(|line| if is_aider_command_ours(line) { desired.as_str() } else { line })
+            .collect::<Vec<_>>()
+            .join("\n");
+        merged.push('\n');
+        return Ok(Some(merged));
     }
 
-    fs::write(&path, serde_json::to_string_pretty(&settings)?)?;
-    Ok(true)
+    let mut merged = content.to_string();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    // The command only runs with notifications on; an explicit setting is kept
+    if yaml_value(content, "notifications").is_none() {
+        merged.push_str("notifications: true\n");
+    }
+    merged.push_str(&desired);
+    merged.push('\n');
+
+    Ok(Some(merged))
+}
+
+fn is_aider_command_ours(line: &str) -> bool {
+    line.strip_prefix("notifications-command:")
+        .map(|value| is_ours(&yaml_command(value)))
+        .unwrap_or(false)
 }
 
-fn check_and_inject_gemini_hooks() -> Result<bool> {
-    let path = get_gemini_settings_path()?;
+/// Aider: `notifications-command` in ~/.aider.conf.yml
+pub struct AiderConfig;
 
-    // If file doesn't exist, create it with hooks
-    if !path.exists() {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+impl HookConfig for AiderConfig {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(vec![home_path(".aider.conf.yml")?])
+    }
+
+    fn agent_present(&self, path: &Path) -> bool {
+        // Aider keeps caches in ~/.aider once it has been used
+        path.exists() || path.with_file_name(".aider").exists()
+    }
+
+    fn install(&self, content: &str) -> Result<String> {
+        Ok(merge_aider_notifications(content)?.unwrap_or_else(|| content.to_string()))
+    }
+
+    fn uninstall(&self, content: &str) -> Result<String> {
+        // `notifications: true` is kept; it may predate us
+        let kept: Vec<&str> = content
+            .lines()
+            .filter(|line| !is_aider_command_ours(line))
+            .collect();
+        if kept.len() == content.lines().count() {
+            return Ok(content.to_string());
         }
-        let settings = json!({
-            "experiments": { "enableHooks": true },
-            "hooks": gemini_hooks()
-        });
-        fs::write(&path, serde_json::to_string_pretty(&settings)?)?;
-        return Ok(true);
+        let mut text = kept.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        Ok(text)
     }
 
-    // Read existing settings
-    let content = fs::read_to_string(&path)?;
-    let mut settings: Value = serde_json::from_str(&content)?;
+    fn events(&self, content: &str) -> Vec<(&'static str, Wiring)> {
+        let wiring = match yaml_value(content, "notifications-command").map(yaml_command) {
+            Some(command) if same_hook(&command, &aider_command()) => Wiring::Installed,
+            Some(command) if is_ours(&command) => Wiring::Outdated,
+            Some(_) => Wiring::Other,
+            None => Wiring::Missing,
+        };
+        vec![("notifications-command", wiring)]
+    }
+}
 
-    // Check if hooks already exist
-    if has_gemini_hooks(&settings) {
-        return Ok(false);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_codex_notify() {
+        let config = "# my settings\nmodel = \"gpt-5-codex\"\n\n[mcp_servers.docs]\ncommand = \"docs-mcp\"\n";
+
+        let merged = merge_codex_notify(config).unwrap().unwrap();
+        assert!(merged.starts_with("# my settings\n"));
+        assert_eq!(codex_notify_wiring(&parse_codex_config(&merged).unwrap()), Wiring::Installed);
+        // notify must stay a root key, ahead of any table
+        assert!(merged.find("notify").unwrap() < merged.find("[mcp_servers.docs]").unwrap());
+
+        // Already installed: nothing to do
+        assert!(merge_codex_notify(&merged).unwrap().is_none());
+
+        // Uninstalling restores the original file
+        assert_eq!(CodexConfig.uninstall(&merged).unwrap(), config);
+
+        // A notify program from an older version is upgraded in place
+        let legacy = merged.replace(
+            &toml_edit::Array::from_iter(codex_notify(&[])).to_string(),
+            r#"["bash", "-c", "tmux set -p @agent_provider codex", "coder-tools-notify"]"#,
+        );
+        assert_eq!(codex_notify_wiring(&parse_codex_config(&legacy).unwrap()), Wiring::Outdated);
+        assert_eq!(merge_codex_notify(&legacy).unwrap().unwrap(), merged);
+
+        // A user's own notify program is called from ours, and restored on uninstall
+        let user = "notify = [\"notify-send\", \"Codex\"]\n";
+        let chained = merge_codex_notify(user).unwrap().unwrap();
+        let doc = parse_codex_config(&chained).unwrap();
+        assert_eq!(codex_notify_wiring(&doc), Wiring::Installed);
+        assert_eq!(notify_args(&doc)[5..], ["--", "notify-send", "Codex"]);
+        assert!(merge_codex_notify(&chained).unwrap().is_none());
+        assert_eq!(CodexConfig.uninstall(&chained).unwrap(), user);
     }
 
-    // Create backup before modifying
-    create_backup(&path)?;
+    #[test]
+    fn test_merge_aider_notifications() {
+        let merged = merge_aider_notifications("model: sonnet").unwrap().unwrap();
+        assert!(merged.starts_with("model: sonnet\nnotifications: true\n"));
+        assert!(merged.contains(HOOK_MARKER));
+
+        assert!(merge_aider_notifications(&merged).unwrap().is_none());
+        assert_eq!(AiderConfig.uninstall(&merged).unwrap(), "model: sonnet\nnotifications: true\n");
 
-    // Ensure experiments.enableHooks is set
-    if settings.get("experiments").is_none() {
-        settings["experiments"] = json!({});
+        // An explicit notifications setting is respected
+        let merged = merge_aider_notifications("notifications: false\n").unwrap().unwrap();
+        assert!(!merged.contains("notifications: true"));
+
+        assert!(merge_aider_notifications("notifications-command: say done\n").is_err());
     }
-    settings["experiments"]["enableHooks"] = json!(true);
 
-    // Merge hooks into existing settings
-    if settings.get("hooks").is_none() {
-        settings["hooks"] = json!({});
+    #[test]
+    fn test_claude_install_upgrades_legacy_hooks() {
+        // The user's own hook sets a pane option too, but isn't ours
+        let user_hook = json!({"type": "command", "command": "tmux set -p @agent_status idle"});
+        let legacy = json!({"type": "command", "command": "tmux set -p @agent_status waiting 2>/dev/null || true"});
+        let content = serde_json::to_string_pretty(&json!({
+            "hooks": {
+                "Stop": [{"hooks": [user_hook, legacy]}],
+                "PermissionRequest": [{"hooks": [legacy]}, {"hooks": [legacy]}]
+            }
+        }))
+        .unwrap();
+        assert!(ClaudeSettings.events(&content).contains(&("Stop", Wiring::Outdated)));
+
+        let installed: Value = serde_json::from_str(&ClaudeSettings.install(&content).unwrap()).unwrap();
+        let hooks: std::collections::HashMap<_, _> = claude_hooks().into_iter().collect();
+        // Upgraded where it stood, next to the user's hook
+        assert_eq!(installed["hooks"]["Stop"], json!([{"hooks": [user_hook, hook_entry(&hooks["Stop"])]}]));
+        // Duplicates are dropped along with the groups they leave empty
+        assert_eq!(
+            installed["hooks"]["PermissionRequest"],
+            json!([{"hooks": [hook_entry(&hooks["PermissionRequest"])]}])
+        );
+        let removed: Value = serde_json::from_str(&ClaudeSettings.uninstall(&content).unwrap()).unwrap();
+        assert_eq!(removed["hooks"], json!({"Stop": [{"hooks": [user_hook]}]}));
     }
 
-    let hooks_obj = settings["hooks"].as_object_mut().context("hooks is not an object")?;
-    let new_hooks = gemini_hooks();
-    let new_hooks_obj = new_hooks.as_object().unwrap();
+    #[test]
+    fn test_hooks_from_another_install() {
+        assert_eq!(
+            split_program(&format!("{} hook Stop", shell_quote("/opt/it's/coder-tools"))),
+            Some(("/opt/it's/coder-tools".to_string(), " hook Stop"))
+        );
 
-    for (key, value) in new_hooks_obj {
-        if !hooks_obj.contains_key(key) {
-            hooks_obj.insert(key.clone(), value.clone());
-        }
+        let hooks: std::collections::HashMap<_, _> = claude_hooks().into_iter().collect();
+        let (_, args) = split_program(&hooks["Stop"]).unwrap();
+        let settings = |program: &str| {
+            let entry = json!({"type": "command", "command": format!("{}{}", shell_quote(program), args)});
+            serde_json::to_string_pretty(&json!({"hooks": {"Stop": [{"hooks": [entry]}]}})).unwrap() + "\n"
+        };
+
+        // Left alone while the other install's program is still there
+        let other = settings("/bin/sh");
+        assert!(ClaudeSettings.events(&other).contains(&("Stop", Wiring::Installed)));
+        let installed: Value = serde_json::from_str(&ClaudeSettings.install(&other).unwrap()).unwrap();
+        let kept = json!({"type": "command", "command": format!("/bin/sh{}", args)});
+        assert_eq!(installed["hooks"]["Stop"], json!([{"hooks": [kept]}]));
+
+        // Replaced once it is gone
+        let gone = settings("/no such dir/coder-tools");
+        assert!(ClaudeSettings.events(&gone).contains(&("Stop", Wiring::Outdated)));
+        let installed: Value = serde_json::from_str(&ClaudeSettings.install(&gone).unwrap()).unwrap();
+        assert_eq!(installed["hooks"]["Stop"], json!([{"hooks": [hook_entry(&hooks["Stop"])]}]));
     }
 
-    fs::write(&path, serde_json::to_string_pretty(&settings)?)?;
-    Ok(true)
+    #[test]
+    fn test_budget_hooks_leave_status_hooks() {
+        let installed = ClaudeSettings.install("").unwrap();
+        let with_budget = wire_budget_events(&installed, &["UserPromptSubmit", "PreToolUse"]).unwrap();
+
+        let settings: Value = serde_json::from_str(&with_budget).unwrap();
+        assert_eq!(settings["hooks"]["UserPromptSubmit"].as_array().unwrap().len(), 2);
+        assert!(ClaudeSettings.events(&with_budget).iter().all(|(_, w)| *w == Wiring::Installed));
+
+        // Narrowing the events, or uninstalling status hooks, keeps the other kind
+        let prompts_only: Value =
+            serde_json::from_str(&wire_budget_events(&with_budget, &["UserPromptSubmit"]).unwrap()).unwrap();
+        assert_eq!(prompts_only["hooks"]["PreToolUse"].as_array().unwrap().len(), 1);
+        let budget_only: Value = serde_json::from_str(&ClaudeSettings.uninstall(&with_budget).unwrap()).unwrap();
+        let events: Vec<_> = budget_only["hooks"].as_object().unwrap().keys().collect();
+        assert_eq!(events, ["PreToolUse", "UserPromptSubmit"]);
+
+        assert_eq!(wire_budget_events(&with_budget, &[]).unwrap(), installed);
+    }
+
+    #[test]
+    fn test_claude_uninstall_keeps_user_hooks() {
+        let user_hook = json!({"hooks": [{"type": "command", "command": "say done"}]});
+        let content = serde_json::to_string_pretty(&json!({
+            "model": "opus",
+            "hooks": { "Stop": [user_hook] }
+        }))
+        .unwrap();
+
+        let installed = ClaudeSettings.install(&content).unwrap();
+        let events = ClaudeSettings.events(&installed);
+        assert!(events.contains(&("UserPromptSubmit", Wiring::Installed)));
+        assert!(events.contains(&("Stop", Wiring::Installed)));
+        assert_eq!(ClaudeSettings.install(&installed).unwrap(), installed);
+
+        let installed_json: Value = serde_json::from_str(&installed).unwrap();
+        assert_eq!(installed_json["hooks"]["Stop"].as_array().unwrap().len(), 2);
+
+        let removed: Value = serde_json::from_str(&ClaudeSettings.uninstall(&installed).unwrap()).unwrap();
+        assert_eq!(removed["model"], "opus");
+        assert_eq!(removed["hooks"], json!({ "Stop": [user_hook] }));
+    }
 }
@@ -0,0 +1,166 @@
+//! Background data loading for the monitor
+//!
+//! Multiplexer calls and transcript parsing run on tokio's blocking pool and send
+//! their results back over a channel, so the UI thread never waits on them.
+
+use crate::budget::{self, Alert, Gauge};
+use crate::cost::{SessionRef, TokenUsage, UsageTracker};
+use crate::state;
+use crate::usage_index;
+use crate::mux::Pane;
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Result of a background load, applied to the app on the UI thread
+pub enum LoadResult {
+    Panes(Result<Vec<Pane>>),
+    Usage {
+        pane_id: String,
+        transcript: Option<PathBuf>,
+        usage: TokenUsage,
+    },
+    /// Budget gauges, with alerts for thresholds they just crossed
+    Budget(Result<(Vec<Gauge>, Vec<Alert>)>),
+    /// A detached command (see `Loader::run_detached`) failed
+    Failed(String),
+}
+
+pub struct Loader {
+    runtime: Runtime,
+    tx: UnboundedSender<LoadResult>,
+    rx: UnboundedReceiver<LoadResult>,
+    panes_in_flight: Arc<AtomicBool>,
+    budget_in_flight: Arc<AtomicBool>,
+    /// Shared across tasks so each transcript is only parsed incrementally
+    tracker: Arc<Mutex<UsageTracker>>,
+}
+
+impl Loader {
+    pub fn new() -> Result<Self> {
+        let (tx, rx) = unbounded_channel();
+        Ok(Self {
+            runtime: Runtime::new()?,
+            tx,
+            rx,
+            panes_in_flight: Arc::default(),
+            budget_in_flight: Arc::default(),
+            tracker: Arc::new(Mutex::new(UsageTracker::default())),
+        })
+    }
+
+    /// List multiplexer panes and state-file agents in the background (no-op if a listing is already running)
+    pub fn request_panes(&self) {
+        let in_flight = match InFlight::start(&self.panes_in_flight) {
+            Some(guard) => guard,
+            None => return,
+        };
+
+        let tx = self.tx.clone();
+        self.runtime.spawn_blocking(move || {
+            let _in_flight = in_flight;
+            let panes = state::list_panes();
+            let _ = tx.send(LoadResult::Panes(panes));
+        });
+    }
+
+    /// Update token usage for a pane's session in the background
+    pub fn request_usage(&self, pane_id: String, provider: String, session: SessionRef) {
+        let tx = self.tx.clone();
+        let tracker = Arc::clone(&self.tracker);
+        self.runtime.spawn_blocking(move || {
+            let (transcript, usage) = match tracker.lock() {
+                Ok(mut tracker) => tracker.session_usage(&provider, &session),
+                Err(_) => return,
+            };
+            let _ = tx.send(LoadResult::Usage {
+                pane_id,
+                transcript,
+                usage,
+            });
+        });
+    }
+
+    /// Check usage against budget limits in the background (no-op if a check is already running)
+    pub fn request_budget(&self, thresholds: Vec<u8>) {
+        let in_flight = match InFlight::start(&self.budget_in_flight) {
+            Some(guard) => guard,
+            None => return,
+        };
+
+        let tx = self.tx.clone();
+        self.runtime.spawn_blocking(move || {
+            let _in_flight = in_flight;
+            let result = budget::gauges().and_then(|gauges| {
+                let alerts = budget::new_alerts(&gauges, &thresholds)?;
+                Ok((gauges, alerts))
+            });
+            // Rewriting the whole index every minute would cost more than it
+            // saves; it is written once enough is new, and on exit
+            if let Err(e) = usage_index::save_if_behind(usage_index::SAVE_AFTER_BYTES) {
+                let _ = tx.send(LoadResult::Failed(format!("Could not save usage index: {:#}", e)));
+            }
+            let _ = tx.send(LoadResult::Budget(result));
+        });
+    }
+
+    /// Run a fire-and-forget command (e.g. focusing a pane) off the UI thread
+    pub fn run_detached<F>(&self, f: F)
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let tx = self.tx.clone();
+        self.runtime.spawn_blocking(move || {
+            if let Err(e) = f() {
+                let _ = tx.send(LoadResult::Failed(format!("{:#}", e)));
+            }
+        });
+    }
+
+    /// Next finished result, if any
+    pub fn try_recv(&mut self) -> Option<LoadResult> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// Marks a single-flight task as running until it ends, even by panicking,
+/// so a failed task never stops later refreshes
+struct InFlight(Arc<AtomicBool>);
+
+impl InFlight {
+    /// Claim `flag`, or `None` if its task is already running
+    fn start(flag: &Arc<AtomicBool>) -> Option<Self> {
+        if flag.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some(InFlight(Arc::clone(flag)))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_cleared_on_panic() {
+        let flag = Arc::default();
+        let guard = InFlight::start(&flag).unwrap();
+        assert!(InFlight::start(&flag).is_none());
+
+        let task = std::thread::spawn(move || {
+            let _in_flight = guard;
+            panic!("listing failed");
+        });
+        assert!(task.join().is_err());
+        assert!(InFlight::start(&flag).is_some());
+    }
+}
@@ -1,16 +1,27 @@
 mod app;
 mod budget;
+mod config;
 mod cost;
 mod detector;
+mod export;
+mod hook;
 mod hooks;
+mod loader;
+mod metrics;
+mod mux;
 mod notify;
+mod pricing;
+mod profile;
+mod providers;
 mod resume;
+mod state;
 mod sync;
-mod tmux;
 mod ui;
+mod usage_index;
 
 use anyhow::Result;
 use app::App;
+use loader::LoadResult;
 use clap::{Parser, Subcommand};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
@@ -19,20 +30,35 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How often the monitor polls for input and background results
+const UI_TICK: Duration = Duration::from_millis(100);
+
+/// How often the monitor checks usage against budget limits
+const BUDGET_REFRESH: Duration = Duration::from_secs(60);
 
 #[derive(Parser, Debug)]
 #[command(name = "coder-tools")]
 #[command(about = "CLI tools for AI coding assistants (Claude, OpenAI, Gemini)")]
 #[command(version)]
 struct Cli {
+    /// Only use this Claude Code profile (see `profiles` in the config)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Terminal multiplexer to drive (default: the one we run inside, else tmux)
+    #[arg(long, global = true, value_parser = ["tmux", "zellij", "screen"])]
+    mux: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    /// Monitor AI coding sessions across tmux panes
+    /// Monitor AI coding sessions across tmux, zellij or screen panes
     Monitor {
         /// Refresh interval in seconds
         #[arg(short, long, default_value = "2")]
@@ -53,9 +79,17 @@ enum Commands {
         /// Auto-jump to pane when it becomes ready
         #[arg(short, long)]
         jump: bool,
+
+        /// Serve Prometheus metrics on this local port (e.g. 9464)
+        #[arg(long)]
+        metrics_port: Option<u16>,
+
+        /// Don't add missing status hooks to agent settings on start
+        #[arg(long)]
+        no_install_hooks: bool,
     },
 
-    /// List and restore previous Claude Code sessions
+    /// List and restore previous Claude Code and Codex sessions
     Resume {
         #[command(subcommand)]
         action: resume::ResumeAction,
@@ -67,15 +101,48 @@ enum Commands {
         action: sync::SyncAction,
     },
 
+    /// Inspect, install or remove agent status hooks
+    Hooks {
+        #[command(subcommand)]
+        action: hooks::HooksAction,
+    },
+
+    /// Publish an agent's status to its pane and state file (run by agent hooks)
+    Hook(hook::HookArgs),
+
     /// Track and manage token budgets
     Budget {
         #[command(subcommand)]
         action: budget::BudgetAction,
     },
+
+    /// Export monitor stats for the current agent panes
+    Export {
+        /// Output format, repeatable (default: export_formats from config, or all)
+        #[arg(short, long, value_enum)]
+        format: Vec<export::ExportFormat>,
+
+        /// Output directory (default: export_dir from config)
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+
+        /// Include all panes, not just agent sessions
+        #[arg(short, long)]
+        all: bool,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    profile::select(cli.profile.as_deref())?;
+    // Agents, pricing and profiles fall back to defaults without a readable
+    // config; hooks stay quiet, as they must never get in the agent's way
+    if !matches!(cli.command, Commands::Hook(_)) {
+        if let Err(e) = config::shared() {
+            eprintln!("Warning: {:#}; using defaults", e);
+        }
+    }
+    mux::select(cli.mux.as_deref())?;
 
     match cli.command {
         Commands::Monitor {
@@ -84,11 +151,16 @@ fn main() -> Result<()> {
             compact,
             notify,
             jump,
-        } => run_monitor(interval, all, compact, notify, jump),
+            metrics_port,
+            no_install_hooks,
+        } => run_monitor(interval, all, compact, notify, jump, metrics_port, !no_install_hooks),
 
         Commands::Resume { action } => resume::run(action),
         Commands::Sync { action } => sync::run(action),
+        Commands::Hooks { action } => hooks::run(action),
+        Commands::Hook(args) => hook::run(args),
         Commands::Budget { action } => budget::run(action),
+        Commands::Export { format, dir, all } => export::run(format, dir, all),
     }
 }
 
@@ -98,12 +170,33 @@ fn run_monitor(
     compact: bool,
     notify_enabled: bool,
     jump_enabled: bool,
+    metrics_port: Option<u16>,
+    install_hooks: bool,
 ) -> Result<()> {
-    // Auto-inject hooks if missing
-    if let Err(e) = hooks::ensure_hooks_installed() {
-        eprintln!("Warning: Failed to check/install hooks: {}", e);
+    let config = config::shared().cloned().unwrap_or_default();
+
+    // Add missing hooks unless disabled by flag or config
+    let installed_hooks = if install_hooks && config.auto_install_hooks.unwrap_or(true) {
+        hooks::ensure_hooks_installed()
+    } else {
+        Vec::new()
+    };
+
+    for error in providers::config_errors() {
+        eprintln!("Warning: Skipping agent {}", error);
     }
 
+    // Start metrics endpoint before taking over the terminal so bind errors are visible
+    let metrics = match metrics_port {
+        Some(port) => Some(metrics::MetricsServer::spawn(&format!("127.0.0.1:{}", port))?),
+        None => None,
+    };
+    let context = MonitorContext {
+        config,
+        metrics,
+        installed_hooks,
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -112,7 +205,15 @@ fn run_monitor(
     let mut terminal = Terminal::new(backend)?;
 
     // Run app
-    let result = run_monitor_app(&mut terminal, interval, all, compact, notify_enabled, jump_enabled);
+    let result = run_monitor_app(
+        &mut terminal,
+        interval,
+        all,
+        compact,
+        notify_enabled,
+        jump_enabled,
+        &context,
+    );
 
     // Restore terminal
     disable_raw_mode()?;
@@ -123,9 +224,21 @@ fn run_monitor(
     )?;
     terminal.show_cursor()?;
 
+    if let Err(e) = usage_index::save() {
+        eprintln!("Warning: Could not save usage index: {:#}", e);
+    }
+
     result
 }
 
+/// Resources shared with the monitor event loop
+struct MonitorContext {
+    config: config::Config,
+    metrics: Option<metrics::MetricsServer>,
+    /// Agents whose settings got status hooks on start
+    installed_hooks: Vec<&'static str>,
+}
+
 fn run_monitor_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     interval: u64,
@@ -133,19 +246,81 @@ fn run_monitor_app<B: ratatui::backend::Backend>(
     compact: bool,
     notify_enabled: bool,
     jump_enabled: bool,
+    context: &MonitorContext,
 ) -> Result<()> {
     let mut app = App::new(0, all, compact); // 0 is unused placeholder
+    let mut loader = loader::Loader::new()?;
     let refresh_interval = Duration::from_secs(interval);
 
-    // Initial refresh
-    let _ = app.refresh()?;
+    if !context.installed_hooks.is_empty() {
+        app.show_toast(
+            format!(
+                "Installed status hooks for {} (see `coder-tools hooks status`)",
+                context.installed_hooks.join(", ")
+            ),
+            false,
+        );
+    }
+
+    // Initial refresh (rendered as "loading" until the listing arrives)
+    loader.request_panes();
+    let mut last_refresh = Instant::now();
+    loader.request_budget(context.config.budget_alerts());
+    let mut last_budget = Instant::now();
 
     loop {
+        // Apply finished background loads
+        while let Some(result) = loader.try_recv() {
+            match result {
+                LoadResult::Panes(Ok(panes)) => {
+                    let notifications = app.apply_panes(panes);
+                    handle_notifications(&loader, notifications, notify_enabled, jump_enabled);
+
+                    // Costs follow transcripts live; unchanged files are skipped cheaply
+                    for (pane_id, provider, session) in app.begin_cost_refresh() {
+                        loader.request_usage(pane_id, provider, session);
+                    }
+                }
+                LoadResult::Panes(Err(e)) => {
+                    app.show_toast(format!("{}: {}", mux::active().name(), e), true);
+                }
+                LoadResult::Usage {
+                    pane_id,
+                    transcript,
+                    usage,
+                } => app.apply_usage(&pane_id, transcript, usage),
+                LoadResult::Budget(Ok((gauges, alerts))) => {
+                    app.budget_gauges = gauges;
+                    for alert in alerts {
+                        if notify_enabled {
+                            notify::send_notification(&alert.title, &alert.message);
+                        }
+                        app.show_toast(format!("{}: {}", alert.title, alert.message), alert.exceeded);
+                    }
+                }
+                LoadResult::Budget(Err(e)) => app.show_toast(format!("Budget: {:#}", e), true),
+                LoadResult::Failed(message) => app.show_toast(message, true),
+            }
+
+            if let Some(ref metrics) = context.metrics {
+                metrics.update(&app);
+            }
+        }
+
         // Render
         terminal.draw(|frame| ui::render(frame, &app))?;
 
-        // Poll for events with timeout (for auto-refresh)
-        if event::poll(refresh_interval)? {
+        if last_refresh.elapsed() >= refresh_interval {
+            loader.request_panes();
+            last_refresh = Instant::now();
+        }
+        if last_budget.elapsed() >= BUDGET_REFRESH {
+            loader.request_budget(context.config.budget_alerts());
+            last_budget = Instant::now();
+        }
+
+        // Poll for events with a short tick so background results show up promptly
+        if event::poll(UI_TICK)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
                     match key.code {
@@ -153,97 +328,86 @@ fn run_monitor_app<B: ratatui::backend::Backend>(
                             return Ok(());
                         }
                         KeyCode::Char('r') => {
-                            for notif in app.refresh()? {
-                                if notify_enabled {
-                                    if notif.is_permission {
-                                        notify::send_notification(
-                                            &format!("⚠️ Permission: {}", notif.folder_name),
-                                            &format!("{} needs approval", notif.pane_name),
-                                        );
-                                    } else {
-                                        notify::send_notification(
-                                            &format!("Claude ready: {}", notif.folder_name),
-                                            &format!("{} is waiting for input", notif.pane_name),
-                                        );
-                                    }
-                                }
-                                if jump_enabled {
-                                    let _ = tmux::switch_to_pane(
-                                        &notif.session_name,
-                                        notif.window_index,
-                                        notif.pane_index,
-                                    );
-                                    break;
-                                }
-                            }
+                            loader.request_panes();
+                            last_refresh = Instant::now();
                         }
                         KeyCode::Char('a') => app.toggle_show_all(),
                         KeyCode::Char('c') => app.toggle_compact(),
-                        KeyCode::Char('$') => app.refresh_costs(),
+                        KeyCode::Char('$') => {
+                            for (pane_id, provider, session) in app.begin_cost_refresh() {
+                                loader.request_usage(pane_id, provider, session);
+                            }
+                        }
                         KeyCode::Char('w') => app.toggle_filter_working(),
                         KeyCode::Char('i') => app.toggle_filter_waiting(),
                         KeyCode::Char('g') => app.toggle_grouping(),
                         KeyCode::Char('s') => app.toggle_stats(),
-                        KeyCode::Char('e') => {
-                            let export = app.export_stats();
-                            if let Ok(json) = serde_json::to_string_pretty(&export) {
-                                let filename = format!("claude-stats-{}.json", export.timestamp);
-                                let _ = std::fs::write(&filename, json);
-                            }
-                        }
+                        KeyCode::Char('e') => match export::export_from_monitor(&app, &context.config) {
+                            Ok(message) => app.show_toast(message, false),
+                            Err(e) => app.show_toast(format!("Export failed: {}", e), true),
+                        },
                         KeyCode::Tab => {
                             if let Some(pane_state) = app.selected_pane() {
-                                let session = pane_state.pane.session_name.clone();
+                                let session = pane_state.group_label();
                                 app.toggle_session_collapse(&session);
                             }
                         }
                         KeyCode::Down | KeyCode::Char('j') => app.select_next(),
                         KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
-                        KeyCode::Enter => {
-                            if let Some(pane_state) = app.selected_pane() {
-                                let pane = &pane_state.pane;
-                                let _ = tmux::switch_to_pane(
-                                    &pane.session_name,
-                                    pane.window_index,
-                                    pane.pane_index,
-                                );
+                        KeyCode::Enter => match app.selected_pane() {
+                            Some(pane_state) if pane_state.pane.in_multiplexer() => {
+                                let pane = pane_state.pane.clone();
+                                loader.run_detached(move || mux::active().focus(&pane));
                             }
-                        }
-                        KeyCode::Char('y') => {
-                            if let Some(pane_state) = app.selected_pane() {
-                                let _ = tmux::send_keys(&pane_state.pane.id, "y");
-                                let _ = tmux::send_keys(&pane_state.pane.id, "Enter");
+                            Some(_) => app.show_toast("Not in a multiplexer pane, nothing to jump to".to_string(), true),
+                            None => {}
+                        },
+                        KeyCode::Char('y') => match app.selected_pane() {
+                            Some(pane_state) if pane_state.pane.in_multiplexer() => {
+                                let pane = pane_state.pane.clone();
+                                loader.run_detached(move || mux::active().send_keys(&pane, "y"));
                             }
-                        }
+                            Some(_) => app.show_toast("Not in a multiplexer pane, approve in the agent's terminal".to_string(), true),
+                            None => {}
+                        },
                         _ => {}
                     }
                 }
             }
-        } else {
-            // Timeout expired, refresh data
-            for notif in app.refresh()? {
-                if notify_enabled {
-                    if notif.is_permission {
-                        notify::send_notification(
-                            &format!("⚠️ Permission: {}", notif.folder_name),
-                            &format!("{} needs approval", notif.pane_name),
-                        );
-                    } else {
-                        notify::send_notification(
-                            &format!("Claude ready: {}", notif.folder_name),
-                            &format!("{} is waiting for input", notif.pane_name),
-                        );
-                    }
-                }
-                if jump_enabled {
-                    let _ = tmux::switch_to_pane(
-                        &notif.session_name,
-                        notif.window_index,
-                        notif.pane_index,
-                    );
-                    break;
-                }
+        }
+    }
+}
+
+/// Send desktop notifications and auto-jump for state changes
+fn handle_notifications(
+    loader: &loader::Loader,
+    notifications: Vec<app::StateChangeNotification>,
+    notify_enabled: bool,
+    jump_enabled: bool,
+) {
+    for notif in notifications {
+        if notify_enabled {
+            if notif.is_permission {
+                notify::send_notification(
+                    &format!("⚠️ Permission: {}", notif.folder_name),
+                    &format!("{} needs approval", notif.pane_name),
+                );
+            } else {
+                let agent = notif
+                    .provider
+                    .as_deref()
+                    .and_then(providers::get)
+                    .map(|p| p.display_name())
+                    .unwrap_or("Agent");
+                notify::send_notification(
+                    &format!("{} ready: {}", agent, notif.folder_name),
+                    &format!("{} is waiting for input", notif.pane_name),
+                );
             }
         }
+        if jump_enabled && notif.pane.in_multiplexer() {
+            loader.run_detached(move || mux::active().focus(&notif.pane));
+            break;
+        }
     }
 }
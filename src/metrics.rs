@@ -0,0 +1,340 @@
+//! Prometheus / OpenMetrics exporter for the monitor
+//!
+//! Serves the current pane state as text exposition format on a local
+//! HTTP endpoint (`GET /metrics`). The monitor re-renders the body after
+//! every refresh; the server thread only hands out the latest snapshot.
+
+use crate::app::App;
+use crate::detector::Status;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Handle to a running metrics endpoint
+pub struct MetricsServer {
+    body: Arc<Mutex<String>>,
+}
+
+impl MetricsServer {
+    /// Bind to `addr` and serve metrics from a background thread
+    pub fn spawn(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .with_context(|| format!("Failed to bind metrics endpoint on {}", addr))?;
+        Ok(Self::serve(listener))
+    }
+
+    /// Serve metrics on an already bound listener
+    pub fn serve(listener: TcpListener) -> Self {
+        let body = Arc::new(Mutex::new(String::new()));
+
+        let shared = Arc::clone(&body);
+        thread::spawn(move || {
+            // A thread per connection, so a slow client never holds up a scrape
+            for stream in listener.incoming().flatten() {
+                let body = Arc::clone(&shared);
+                thread::spawn(move || handle_connection(stream, &body));
+            }
+        });
+
+        Self { body }
+    }
+
+    /// Replace the served snapshot with the current app state
+    pub fn update(&self, app: &App) {
+        let rendered = render(app);
+        if let Ok(mut body) = self.body.lock() {
+            *body = rendered;
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, body: &Mutex<String>) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Drain headers so clients don't see a reset before the response
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let (status, payload) = match (method, path) {
+        ("GET", "/metrics") | ("GET", "/") => {
+            ("200 OK", body.lock().map(|b| b.clone()).unwrap_or_default())
+        }
+        _ => ("404 Not Found", String::from("not found\n")),
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        CONTENT_TYPE,
+        payload.len(),
+        payload
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Render all metrics for the current app state
+pub fn render(app: &App) -> String {
+    let mut out = String::new();
+
+    // Pane counts by status and provider
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for state in app.pane_states.values() {
+        if state.status.status == Status::NotDetected {
+            continue;
+        }
+        let provider = state.pane.agent_provider.as_deref().unwrap_or("unknown");
+        *counts.entry((status_label(state.status.status), provider)).or_insert(0) += 1;
+    }
+
+    header(&mut out, "coder_tools_panes", "gauge", "Number of agent panes by status and provider");
+    for ((status, provider), count) in &counts {
+        let _ = writeln!(
+            out,
+            "coder_tools_panes{{status=\"{}\",provider=\"{}\"}} {}",
+            status,
+            escape(provider),
+            count
+        );
+    }
+
+    let mut panes: Vec<_> = app
+        .pane_states
+        .values()
+        .filter(|p| p.status.status != Status::NotDetected)
+        .collect();
+    panes.sort_by(|a, b| a.pane.id.cmp(&b.pane.id));
+
+    header(
+        &mut out,
+        "coder_tools_pane_state_seconds_total",
+        "counter",
+        "Seconds each pane has spent in each state",
+    );
+    for state in &panes {
+        let labels = pane_labels(state);
+        let times = state.time_in_states();
+        for (name, secs) in [
+            ("working", times.total_working_secs),
+            ("waiting", times.total_waiting_secs),
+            ("permission", times.total_permission_secs),
+        ] {
+            let _ = writeln!(
+                out,
+                "coder_tools_pane_state_seconds_total{{{},state=\"{}\"}} {}",
+                labels, name, secs
+            );
+        }
+    }
+
+    header(
+        &mut out,
+        "coder_tools_pane_state_changes_total",
+        "counter",
+        "Number of status changes observed per pane",
+    );
+    for state in &panes {
+        let _ = writeln!(
+            out,
+            "coder_tools_pane_state_changes_total{{{}}} {}",
+            pane_labels(state),
+            state.stats.state_changes
+        );
+    }
+
+    header(
+        &mut out,
+        "coder_tools_tokens",
+        "gauge",
+        "Tokens used by the pane's current session",
+    );
+    for state in &panes {
+        if let Some(ref tokens) = state.tokens {
+            let labels = pane_labels(state);
+            for (kind, count) in [
+                ("input", tokens.input_tokens),
+                ("output", tokens.output_tokens),
+                ("cache_read", tokens.cache_read_tokens),
+                ("cache_write", tokens.cache_write_tokens),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "coder_tools_tokens{{{},type=\"{}\"}} {}",
+                    labels, kind, count
+                );
+            }
+        }
+    }
+
+    header(
+        &mut out,
+        "coder_tools_cost_usd",
+        "gauge",
+        "Estimated cost in USD of the pane's current session",
+    );
+    for state in &panes {
+        if let Some(ref tokens) = state.tokens {
+            let _ = writeln!(
+                out,
+                "coder_tools_cost_usd{{{}}} {:.6}",
+                pane_labels(state),
+                tokens.cost_usd()
+            );
+        }
+    }
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn pane_labels(state: &crate::app::PaneState) -> String {
+    format!(
+        "pane=\"{}\",session=\"{}\",path=\"{}\",provider=\"{}\"",
+        escape(&state.pane.display_name()),
+        escape(&state.pane.session_name),
+        escape(&state.pane.current_path),
+        escape(state.pane.agent_provider.as_deref().unwrap_or("unknown"))
+    )
+}
+
+fn status_label(status: Status) -> &'static str {
+    match status {
+        Status::Working => "working",
+        Status::WaitingForInput => "waiting",
+        Status::PermissionRequired => "permission",
+        Status::NotDetected => "none",
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{PaneState, PaneStats};
+    use crate::cost::TokenUsage;
+    use crate::detector::DetectionResult;
+    use crate::mux::Pane;
+    use std::io::Read;
+    use std::time::Instant;
+
+    fn test_app() -> App {
+        let mut app = App::new(0, false, false);
+        let pane = Pane {
+            id: "%1".to_string(),
+            session_name: "dev".to_string(),
+            window_index: 1,
+            pane_index: 0,
+            current_path: "/home/user/proj".to_string(),
+            current_command: "2.1.7".to_string(),
+            agent_provider: Some("claude".to_string()),
+            agent_status: Some("working".to_string()),
+            agent_task: None,
+            agent_session_id: None,
+            agent_transcript: None,
+            agent_cwd: None,
+            agent_tool: None,
+            agent_profile: None,
+            agent_pid: None,
+        };
+        app.pane_states.insert(
+            pane.id.clone(),
+            PaneState {
+                pane,
+                status: DetectionResult {
+                    status: Status::Working,
+                    task: None,
+                },
+                last_change: Instant::now(),
+                status_changed_at: Instant::now(),
+                previous_status: None,
+                stats: PaneStats {
+                    total_working_secs: 0,
+                    total_waiting_secs: 30,
+                    total_permission_secs: 0,
+                    state_changes: 2,
+                },
+                tokens: Some(TokenUsage {
+                    input_tokens: 1_000,
+                    output_tokens: 200,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    by_model: Default::default(),
+                }),
+                tokens_loading: false,
+                transcript: None,
+            },
+        );
+        app
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let body = render(&test_app());
+        assert!(body.contains("coder_tools_panes{status=\"working\",provider=\"claude\"} 1"));
+        assert!(body.contains("state=\"waiting\"} 30"));
+        assert!(body.contains(
+            "coder_tools_pane_state_changes_total{pane=\"dev:1.0\",session=\"dev\",path=\"/home/user/proj\",provider=\"claude\"} 2"
+        ));
+        assert!(body.contains("type=\"input\"} 1000"));
+        // Session totals drop when a pane starts a new session, so they are gauges
+        assert!(body.contains("# TYPE coder_tools_cost_usd gauge"));
+        assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+    }
+
+    #[test]
+    fn test_serves_metrics_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = MetricsServer::serve(listener);
+        server.update(&test_app());
+
+        // An idle client doesn't hold up the others
+        let _idle = TcpStream::connect(addr).unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("coder_tools_panes{status=\"working\",provider=\"claude\"} 1"));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /nope HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,218 @@
+//! Terminal multiplexers the monitor can drive
+//!
+//! Listing panes, focusing one, typing into it and reading its screen sit
+//! behind `Multiplexer`, with tmux, zellij and GNU screen backends. The
+//! backend is the one named by `--mux`, else the one this process runs
+//! inside, else tmux.
+
+pub mod screen;
+pub mod tmux;
+pub mod zellij;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How many 20ms waits for a screen dump that is written asynchronously
+const DUMP_WAIT_TRIES: usize = 10;
+
+pub trait Multiplexer: Sync {
+    /// Name used by `--mux` and recorded in agent state files
+    fn name(&self) -> &'static str;
+
+    /// Whether this process runs inside the multiplexer
+    fn is_inside(&self) -> bool;
+
+    /// Id of the pane this process runs in, as `list_panes` reports it
+    fn current_pane_id(&self) -> Option<String>;
+
+    /// Every pane the multiplexer can see, across sessions
+    fn list_panes(&self) -> Result<Vec<Pane>>;
+
+    /// Bring a pane to the front of the user's client
+    fn focus(&self, pane: &Pane) -> Result<()>;
+
+    /// Type `text` into a pane and press Enter
+    fn send_keys(&self, pane: &Pane, text: &str) -> Result<()>;
+
+    /// Visible text of a pane, including `history` lines of scrollback
+    fn capture(&self, pane: &Pane, history: u32) -> Result<String>;
+}
+
+static BACKENDS: &[&dyn Multiplexer] = &[&tmux::Tmux, &zellij::Zellij, &screen::Screen];
+
+static SELECTED: OnceLock<&'static dyn Multiplexer> = OnceLock::new();
+
+/// Use the backend named by `--mux` instead of detecting one
+pub fn select(name: Option<&str>) -> Result<()> {
+    if let Some(name) = name {
+        let backend = BACKENDS.iter().copied().find(|b| b.name() == name).with_context(|| {
+            let names: Vec<_> = BACKENDS.iter().map(|b| b.name()).collect();
+            format!("Unknown multiplexer '{}' (expected one of: {})", name, names.join(", "))
+        })?;
+        let _ = SELECTED.set(backend);
+    }
+    Ok(())
+}
+
+/// Backend to drive: the selected one, the one we run inside, or tmux
+pub fn active() -> &'static dyn Multiplexer {
+    if let Some(backend) = SELECTED.get() {
+        return *backend;
+    }
+    inside().unwrap_or(&tmux::Tmux)
+}
+
+/// The multiplexer this process runs inside, if any
+pub fn inside() -> Option<&'static dyn Multiplexer> {
+    BACKENDS.iter().copied().find(|b| b.is_inside())
+}
+
+#[derive(Debug, Clone)]
+pub struct Pane {
+    pub id: String,
+    pub session_name: String,
+    pub window_index: u32,
+    pub pane_index: u32,
+    pub current_path: String,
+    /// Current command running in the pane (for validating agent is still running)
+    pub current_command: String,
+    /// Agent provider from hook-published @agent_provider option (claude, gemini, codex)
+    pub agent_provider: Option<String>,
+    /// Agent status from hook-published @agent_status option
+    pub agent_status: Option<String>,
+    /// Agent task from hook-published @agent_task option
+    pub agent_task: Option<String>,
+    /// Agent session id from hook-published @agent_session_id option
+    pub agent_session_id: Option<String>,
+    /// Session transcript path from hook-published @agent_transcript option
+    pub agent_transcript: Option<String>,
+    /// Agent's working directory from hook-published @agent_cwd option
+    pub agent_cwd: Option<String>,
+    /// Tool in use or awaiting permission, from hook-published @agent_tool option
+    pub agent_tool: Option<String>,
+    /// Claude Code profile from hook-published @agent_profile option
+    pub agent_profile: Option<String>,
+    /// Agent process id, for agents found through the state directory rather
+    /// than the multiplexer
+    pub agent_pid: Option<u32>,
+}
+
+impl Pane {
+    /// A pane as the multiplexer lists it, before any agent details are known
+    pub fn new(id: String, session_name: String, window_index: u32, pane_index: u32, current_command: String) -> Self {
+        Pane {
+            id,
+            session_name,
+            window_index,
+            pane_index,
+            current_path: String::new(),
+            current_command,
+            agent_provider: None,
+            agent_status: None,
+            agent_task: None,
+            agent_session_id: None,
+            agent_transcript: None,
+            agent_cwd: None,
+            agent_tool: None,
+            agent_profile: None,
+            agent_pid: None,
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        match self.agent_pid {
+            Some(pid) => format!("pid {}", pid),
+            None => format!(
+                "{}:{}.{}",
+                self.session_name, self.window_index, self.pane_index
+            ),
+        }
+    }
+
+    /// Name within its session, for the grouped view
+    pub fn short_name(&self) -> String {
+        match self.agent_pid {
+            Some(pid) => format!("pid {}", pid),
+            None => format!("{}.{}", self.window_index, self.pane_index),
+        }
+    }
+
+    /// Whether the agent runs in a multiplexer pane that can be jumped to
+    pub fn in_multiplexer(&self) -> bool {
+        self.agent_pid.is_none()
+    }
+}
+
+/// Run a multiplexer command, failing with its stderr if it exits non-zero
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to execute {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Name of the command in a command line: the program's file name, or for a
+/// Python interpreter the script or module it runs, so that
+/// `/usr/bin/python3 ~/.local/bin/aider` and `python -m aider` are "aider"
+pub fn command_name(args: &str) -> String {
+    let mut words = args.split_whitespace();
+    // Paths are cut to their file name, but not a word like "N/A"
+    let file_name = |word: &str| {
+        if word.starts_with(['/', '.', '~']) {
+            word.rsplit('/').next().unwrap_or(word).to_string()
+        } else {
+            word.to_string()
+        }
+    };
+    let program = match words.next() {
+        Some(program) => file_name(program),
+        None => return String::new(),
+    };
+    // macOS's framework build is "Python"
+    if !program.to_ascii_lowercase().starts_with("python") {
+        return program;
+    }
+    while let Some(word) = words.next() {
+        if word == "-m" {
+            return words.next().map(file_name).unwrap_or(program);
+        }
+        if !word.starts_with('-') {
+            return file_name(word);
+        }
+    }
+    program
+}
+
+/// Temporary file for a backend to dump a pane's screen into
+fn dump_path(backend: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("coder-tools-{}-{}-{}.txt", backend, std::process::id(), n))
+}
+
+/// Read and remove a screen dump, waiting briefly for it to be written
+fn read_dump(path: &Path) -> Result<String> {
+    for _ in 0..DUMP_WAIT_TRIES {
+        if path.exists() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let text = fs::read(path).context("Failed to read screen dump")?;
+    let _ = fs::remove_file(path);
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
@@ -0,0 +1,166 @@
+//! GNU screen backend
+//!
+//! Each screen window is one pane, identified as `<session>:<window>` where
+//! the session is the full `pid.name` socket name from `STY`. Screen only
+//! knows window titles, so agents are recognised through their state files
+//! (see `state`).
+
+use super::{dump_path, read_dump, run, Multiplexer, Pane};
+use anyhow::{Context, Result};
+use std::process::Command;
+
+pub struct Screen;
+
+impl Multiplexer for Screen {
+    fn name(&self) -> &'static str {
+        "screen"
+    }
+
+    fn is_inside(&self) -> bool {
+        std::env::var_os("STY").is_some()
+    }
+
+    fn current_pane_id(&self) -> Option<String> {
+        let session = std::env::var("STY").ok()?;
+        let window = std::env::var("WINDOW").ok()?;
+        Some(format!("{}:{}", session, window))
+    }
+
+    fn list_panes(&self) -> Result<Vec<Pane>> {
+        let mut panes = Vec::new();
+        for session in sessions()? {
+            // A session may exit between the two calls
+            let listing = match run("screen", &["-S", &session, "-Q", "windows"]) {
+                Ok(listing) => listing,
+                Err(_) => continue,
+            };
+            let name = session.split_once('.').map(|(_, n)| n).unwrap_or(&session);
+            for (window, title) in parse_windows(&listing) {
+                panes.push(Pane::new(
+                    format!("{}:{}", session, window),
+                    name.to_string(),
+                    window,
+                    0,
+                    title,
+                ));
+            }
+        }
+        Ok(panes)
+    }
+
+    fn focus(&self, pane: &Pane) -> Result<()> {
+        let (session, window) = target(pane)?;
+        run("screen", &["-S", session, "-X", "select", window])?;
+        Ok(())
+    }
+
+    fn send_keys(&self, pane: &Pane, text: &str) -> Result<()> {
+        let (session, window) = target(pane)?;
+        run("screen", &["-S", session, "-p", window, "-X", "stuff", &format!("{}\r", text)])?;
+        Ok(())
+    }
+
+    fn capture(&self, pane: &Pane, history: u32) -> Result<String> {
+        let (session, window) = target(pane)?;
+        let path = dump_path("screen");
+        let path_arg = path.to_string_lossy();
+        let mut args = vec!["-S", session, "-p", window, "-X", "hardcopy"];
+        // Screen can only include all of its scrollback or none of it
+        if history > 0 {
+            args.push("-h");
+        }
+        args.push(&path_arg);
+        run("screen", &args)?;
+        read_dump(&path)
+    }
+}
+
+/// Session and window of a pane id
+fn target(pane: &Pane) -> Result<(&str, &str)> {
+    pane.id
+        .rsplit_once(':')
+        .with_context(|| format!("Not a screen window: {}", pane.id))
+}
+
+/// Socket names (`pid.name`) of running sessions
+fn sessions() -> Result<Vec<String>> {
+    let output = Command::new("screen")
+        .arg("-ls")
+        .output()
+        .context("Failed to execute screen -ls")?;
+
+    // `screen -ls` exits non-zero when listing, so only the output counts
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter(|line| line.starts_with('\t'))
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|socket| {
+            socket
+                .split_once('.')
+                .map(|(pid, _)| pid.parse::<u32>().is_ok())
+                .unwrap_or(false)
+        })
+        .map(str::to_string)
+        .collect())
+}
+
+/// Window numbers and titles from `screen -Q windows` ("0$ bash  1*$ claude")
+///
+/// Entries are joined by two spaces, which a title may contain too, so a new
+/// entry only starts where a window number and its flags are followed by a
+/// space.
+fn parse_windows(listing: &str) -> Vec<(u32, String)> {
+    let mut windows: Vec<(u32, String)> = Vec::new();
+    for segment in listing.trim().split("  ") {
+        match window_header(segment) {
+            Some((window, title)) => windows.push((window, title.trim().to_string())),
+            // Part of the previous title
+            None => {
+                if let Some((_, title)) = windows.last_mut() {
+                    title.push_str("  ");
+                    title.push_str(segment.trim_end());
+                }
+            }
+        }
+    }
+    windows
+}
+
+/// Window number and the rest of an entry starting with `<digits><flags> `
+fn window_header(segment: &str) -> Option<(u32, &str)> {
+    let digits = segment.find(|c: char| !c.is_ascii_digit()).unwrap_or(segment.len());
+    let window = segment[..digits].parse().ok()?;
+    // Flags such as * (current), - (previous), $ (logged in), ! (bell),
+    // @ (activity), & (multiuser) and Z (zombie)
+    let rest = segment[digits..].trim_start_matches(['*', '-', '$', '!', '@', '&', 'Z']);
+    if rest.is_empty() {
+        return Some((window, rest));
+    }
+    rest.strip_prefix(' ').map(|title| (window, title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_windows() {
+        let windows = parse_windows("0$ bash  1*$ claude  12-$ vim notes.md\n");
+        assert_eq!(
+            windows,
+            vec![
+                (0, "bash".to_string()),
+                (1, "claude".to_string()),
+                (12, "vim notes.md".to_string()),
+            ]
+        );
+
+        // A title with a double space stays whole
+        let windows = parse_windows("0$ claude  fix tests  v3 notes  4$ vim\n");
+        assert_eq!(
+            windows,
+            vec![(0, "claude  fix tests  v3 notes".to_string()), (4, "vim".to_string())]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! tmux backend
+//!
+//! Hooks publish agent status straight into tmux pane options (@agent_*),
+//! so one list-panes call reads every agent.
+
+use super::{command_name, Multiplexer, Pane};
+use anyhow::{Context, Result};
+use std::process::Command;
+
+pub struct Tmux;
+
+impl Multiplexer for Tmux {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn is_inside(&self) -> bool {
+        std::env::var_os("TMUX").is_some()
+    }
+
+    fn current_pane_id(&self) -> Option<String> {
+        std::env::var("TMUX_PANE").ok().filter(|id| !id.is_empty())
+    }
+
+    fn list_panes(&self) -> Result<Vec<Pane>> {
+        list_panes()
+    }
+
+    fn focus(&self, pane: &Pane) -> Result<()> {
+        switch_to_pane(&pane.session_name, pane.window_index, pane.pane_index)
+    }
+
+    fn send_keys(&self, pane: &Pane, text: &str) -> Result<()> {
+        send_keys(&pane.id, text)?;
+        send_keys(&pane.id, "Enter")
+    }
+
+    fn capture(&self, pane: &Pane, history: u32) -> Result<String> {
+        let output = Command::new("tmux")
+            .args(["capture-pane", "-p", "-t", &pane.id, "-S", &format!("-{}", history)])
+            .output()
+            .context("Failed to capture pane")?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Switch to a specific pane (works across sessions)
+fn switch_to_pane(session: &str, window: u32, pane: u32) -> Result<()> {
+    let target = format!("{}:{}.{}", session, window, pane);
+
+    // First, switch the client to the target session (enables cross-session navigation)
+    Command::new("tmux")
+        .args(["switch-client", "-t", session])
+        .output()
+        .context("Failed to switch session")?;
+
+    // Then select the window within that session
+    Command::new("tmux")
+        .args(["select-window", "-t", &format!("{}:{}", session, window)])
+        .output()
+        .context("Failed to select window")?;
+
+    // Finally select the specific pane
+    Command::new("tmux")
+        .args(["select-pane", "-t", &target])
+        .output()
+        .context("Failed to select pane")?;
+
+    Ok(())
+}
+
+/// Send keys to a specific pane
+fn send_keys(pane_id: &str, keys: &str) -> Result<()> {
+    Command::new("tmux")
+        .args(["send-keys", "-t", pane_id, keys])
+        .output()
+        .context("Failed to send keys to pane")?;
+
+    Ok(())
+}
+
+/// Set user options (e.g. @agent_status) on a pane in one tmux call
+pub fn set_pane_options(pane_id: &str, options: &[(&str, String)]) -> Result<()> {
+    let mut args: Vec<&str> = Vec::new();
+    for (name, value) in options {
+        if !args.is_empty() {
+            args.push(";");
+        }
+        // `--` keeps values such as "-v flag" from being read as options
+        args.extend(["set", "-p", "-t", pane_id, "--", name, value.as_str()]);
+    }
+
+    let output = Command::new("tmux")
+        .args(&args)
+        .output()
+        .context("Failed to set pane options")?;
+    if !output.status.success() {
+        anyhow::bail!("tmux set failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Format string for list-panes: includes hook-published agent provider, status, task,
+/// session id, transcript path, working directory, tool and profile, then the pane's pid
+const PANE_FORMAT: &str = "#{pane_id}\t#{session_name}\t#{window_index}\t#{pane_index}\t#{pane_current_path}\t#{pane_current_command}\t#{@agent_provider}\t#{@agent_status}\t#{@agent_task}\t#{@agent_session_id}\t#{@agent_transcript}\t#{@agent_cwd}\t#{@agent_tool}\t#{@agent_profile}\t#{pane_pid}";
+
+/// Hook-published option value at `index`, if set and non-empty
+fn option_field(parts: &[&str], index: usize) -> Option<String> {
+    parts.get(index).and_then(|s| {
+        let s = s.trim();
+        if s.is_empty() { None } else { Some(s.to_string()) }
+    })
+}
+
+fn list_panes() -> Result<Vec<Pane>> {
+    let output = Command::new("tmux")
+        .args(["list-panes", "-a", "-F", PANE_FORMAT])
+        .output()
+        .context("Failed to execute tmux list-panes")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("no server running") || stderr.contains("no current client") {
+            return Ok(Vec::new());
+        }
+        anyhow::bail!("tmux list-panes failed: {}", stderr);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut processes = None;
+    let panes = stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() >= 6 {
+                let mut command = parts[5].to_string();
+                // tmux names a script by its interpreter; name it by the script
+                if command.starts_with("python") {
+                    let pane_pid = parts.get(14).and_then(|p| p.trim().parse().ok());
+                    let processes = processes.get_or_insert_with(list_processes);
+                    if let Some(script) = pane_pid.and_then(|pid| foreground_script(processes, pid)) {
+                        command = script;
+                    }
+                }
+                // Hook-published options may be empty
+                Some(Pane {
+                    id: parts[0].to_string(),
+                    session_name: parts[1].to_string(),
+                    window_index: parts[2].parse().unwrap_or(0),
+                    pane_index: parts[3].parse().unwrap_or(0),
+                    current_path: parts[4].to_string(),
+                    current_command: command,
+                    agent_provider: option_field(&parts, 6),
+                    agent_status: option_field(&parts, 7),
+                    agent_task: option_field(&parts, 8),
+                    agent_session_id: option_field(&parts, 9),
+                    agent_transcript: option_field(&parts, 10),
+                    agent_cwd: option_field(&parts, 11),
+                    agent_tool: option_field(&parts, 12),
+                    agent_profile: option_field(&parts, 13),
+                    agent_pid: None,
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    Ok(panes)
+}
+
+/// Pid, parent pid and command line of every process
+fn list_processes() -> Vec<(u32, u32, String)> {
+    let output = match Command::new("ps").args(["-A", "-o", "pid=,ppid=,args="]).output() {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            Some((pid, ppid, fields.collect::<Vec<_>>().join(" ")))
+        })
+        .collect()
+}
+
+/// Script run by the Python interpreter in a pane: the pane's own process
+/// or one of its children
+fn foreground_script(processes: &[(u32, u32, String)], pane_pid: u32) -> Option<String> {
+    let is_python = |args: &str| {
+        let program = args.split_whitespace().next().unwrap_or("");
+        program.rsplit('/').next().unwrap_or(program).starts_with("python")
+    };
+    processes
+        .iter()
+        .filter(|(pid, ppid, args)| (*pid == pane_pid || *ppid == pane_pid) && is_python(args))
+        .map(|(_, _, args)| command_name(args))
+        .find(|name| !name.starts_with("python"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pane_display_name() {
+        let pane = Pane {
+            id: "%0".to_string(),
+            session_name: "dev".to_string(),
+            window_index: 1,
+            pane_index: 0,
+            current_path: "/home/user".to_string(),
+            current_command: "2.1.7".to_string(),
+            agent_provider: Some("claude".to_string()),
+            agent_status: Some("working".to_string()),
+            agent_task: Some("fix the bug".to_string()),
+            agent_session_id: None,
+            agent_transcript: None,
+            agent_cwd: None,
+            agent_tool: None,
+            agent_profile: None,
+            agent_pid: None,
+        };
+        assert_eq!(pane.display_name(), "dev:1.0");
+    }
+
+    #[test]
+    fn test_foreground_script() {
+        let processes = vec![
+            (100, 1, "-bash".to_string()),
+            (200, 100, "/usr/bin/python3 /home/u/.local/bin/aider --model sonnet".to_string()),
+            (300, 1, "zsh".to_string()),
+            (400, 300, "python3".to_string()),
+            (500, 1, "python -m aider".to_string()),
+        ];
+        assert_eq!(foreground_script(&processes, 100).as_deref(), Some("aider"));
+        // A REPL runs no script
+        assert_eq!(foreground_script(&processes, 300), None);
+        // The interpreter can be the pane's own process
+        assert_eq!(foreground_script(&processes, 500).as_deref(), Some("aider"));
+    }
+}
@@ -0,0 +1,194 @@
+//! zellij backend
+//!
+//! zellij's CLI reports the pane each client has focused, not the full
+//! layout, so those are the panes listed here; agents elsewhere still show
+//! up through their state files (see `state`). Panes are identified as
+//! `<session>:terminal_<n>`, matching `ZELLIJ_PANE_ID` in the agent's env.
+//! Actions only reach the focused pane, so focusing steps through panes
+//! and tabs until the pane has it, and keys and captures are refused for a
+//! pane that has lost focus.
+
+use super::{command_name, dump_path, read_dump, run, Multiplexer, Pane};
+use anyhow::{Context, Result};
+
+/// Most panes stepped through in one tab while looking for a pane to focus
+const MAX_TAB_PANES: usize = 64;
+
+pub struct Zellij;
+
+impl Multiplexer for Zellij {
+    fn name(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn is_inside(&self) -> bool {
+        std::env::var_os("ZELLIJ").is_some()
+    }
+
+    fn current_pane_id(&self) -> Option<String> {
+        let session = std::env::var("ZELLIJ_SESSION_NAME").ok()?;
+        let pane = std::env::var("ZELLIJ_PANE_ID").ok()?;
+        Some(format!("{}:terminal_{}", session, pane))
+    }
+
+    fn list_panes(&self) -> Result<Vec<Pane>> {
+        let mut panes: Vec<Pane> = Vec::new();
+        for session in sessions() {
+            // A session may exit between the two calls
+            let clients = match run("zellij", &["--session", &session, "action", "list-clients"]) {
+                Ok(clients) => clients,
+                Err(_) => continue,
+            };
+            for (pane_index, command) in parse_clients(&clients) {
+                let id = format!("{}:terminal_{}", session, pane_index);
+                // Several clients can share a focused pane
+                if panes.iter().any(|p| p.id == id) {
+                    continue;
+                }
+                panes.push(Pane::new(id, session.clone(), 0, pane_index, command));
+            }
+        }
+        Ok(panes)
+    }
+
+    fn focus(&self, pane: &Pane) -> Result<()> {
+        match std::env::var("ZELLIJ_SESSION_NAME") {
+            Ok(current) if current == pane.session_name => {}
+            _ => anyhow::bail!(
+                "zellij can't switch sessions from the command line; attach to '{}'",
+                pane.session_name
+            ),
+        }
+
+        // The CLI can't focus a pane by id, so step through each tab's panes
+        // until it has focus; a full round leaves focus where it was
+        let target = [terminal_index(pane)?];
+        let session = pane.session_name.as_str();
+        let tabs = run("zellij", &["--session", session, "action", "query-tab-names"])
+            .map(|names| names.lines().count())
+            .unwrap_or(1)
+            .max(1);
+        for _ in 0..tabs {
+            let first = focused_panes(session)?;
+            for _ in 0..MAX_TAB_PANES {
+                if focused_panes(session)? == target {
+                    return Ok(());
+                }
+                run("zellij", &["--session", session, "action", "focus-next-pane"])?;
+                if focused_panes(session)? == first {
+                    break;
+                }
+            }
+            run("zellij", &["--session", session, "action", "go-to-next-tab"])?;
+        }
+        anyhow::bail!("Could not find {} in zellij session '{}' to focus it", pane.id, session)
+    }
+
+    fn send_keys(&self, pane: &Pane, text: &str) -> Result<()> {
+        ensure_focused(pane)?;
+        let session = pane.session_name.as_str();
+        run("zellij", &["--session", session, "action", "write-chars", text])?;
+        run("zellij", &["--session", session, "action", "write", "13"])?;
+        Ok(())
+    }
+
+    fn capture(&self, pane: &Pane, history: u32) -> Result<String> {
+        ensure_focused(pane)?;
+        let path = dump_path("zellij");
+        let path_arg = path.to_string_lossy();
+        let mut args = vec!["--session", pane.session_name.as_str(), "action", "dump-screen", &path_arg];
+        // zellij can only include all of its scrollback or none of it
+        if history > 0 {
+            args.push("--full");
+        }
+        run("zellij", &args)?;
+        read_dump(&path)
+    }
+}
+
+/// Fail unless `pane` is the one the session's actions go to
+///
+/// zellij's CLI can't address a pane by id: `write-chars` and `dump-screen`
+/// act on the focused pane. Keys are only sent while every client of the
+/// session has `pane` focused, so they never land in another pane.
+fn ensure_focused(pane: &Pane) -> Result<()> {
+    if focused_panes(&pane.session_name)? != [terminal_index(pane)?] {
+        anyhow::bail!(
+            "zellij can only reach the focused pane; focus {} in '{}' first",
+            pane.id,
+            pane.session_name
+        );
+    }
+    Ok(())
+}
+
+/// The `<n>` of a `<session>:terminal_<n>` pane id
+fn terminal_index(pane: &Pane) -> Result<u32> {
+    pane.id
+        .rsplit_once(":terminal_")
+        .and_then(|(_, n)| n.parse().ok())
+        .with_context(|| format!("Not a zellij terminal pane: {}", pane.id))
+}
+
+/// Terminal panes focused by the session's clients, in order
+fn focused_panes(session: &str) -> Result<Vec<u32>> {
+    let clients = run("zellij", &["--session", session, "action", "list-clients"])?;
+    let mut focused: Vec<u32> = parse_clients(&clients).into_iter().map(|(index, _)| index).collect();
+    focused.sort_unstable();
+    focused.dedup();
+    Ok(focused)
+}
+
+/// Names of running sessions (exited, resurrectable ones are skipped)
+fn sessions() -> Vec<String> {
+    // Fails with "No active zellij sessions found" when there are none
+    run("zellij", &["list-sessions", "--no-formatting"])
+        .map(|listing| {
+            listing
+                .lines()
+                .filter(|line| !line.contains("EXITED"))
+                .filter_map(|line| line.split_whitespace().next())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Terminal pane numbers and command names from `list-clients`
+///
+/// ```text
+/// CLIENT_ID ZELLIJ_PANE_ID RUNNING_COMMAND
+/// 1         terminal_3     claude --resume
+/// ```
+fn parse_clients(listing: &str) -> Vec<(u32, String)> {
+    listing
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (_client, rest) = line.trim().split_once(char::is_whitespace)?;
+            let rest = rest.trim_start();
+            let (pane, command) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let pane = pane.strip_prefix("terminal_")?.parse().ok()?;
+            // A full path is cut to its file name, but "N/A" (a shell) is kept
+            Some((pane, command_name(command)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_clients() {
+        let listing = "CLIENT_ID ZELLIJ_PANE_ID RUNNING_COMMAND\n\
+                       1         terminal_3     /usr/local/bin/claude --resume\n\
+                       2 plugin_1 N/A\n\
+                       3 terminal_0 N/A\n\
+                       4 terminal_5 /usr/bin/python3 /home/u/.local/bin/aider --model sonnet\n";
+        assert_eq!(
+            parse_clients(listing),
+            vec![(3, "claude".to_string()), (0, "N/A".to_string()), (5, "aider".to_string())]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Per-model token pricing
+//!
+//! Built-in USD rates per million tokens, matched by longest model-name
+//! prefix. Entries in the config's `pricing` map override or extend them.
+
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    /// 5-minute cache writes
+    pub cache_write_5m: f64,
+    /// 1-hour cache writes
+    pub cache_write_1h: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64, cache_read: f64, cache_write_5m: f64, cache_write_1h: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read,
+            cache_write_5m,
+            cache_write_1h,
+        }
+    }
+}
+
+const SONNET: ModelPricing = ModelPricing::new(3.0, 15.0, 0.30, 3.75, 6.0);
+const OPUS: ModelPricing = ModelPricing::new(15.0, 75.0, 1.50, 18.75, 30.0);
+const OPUS_45: ModelPricing = ModelPricing::new(5.0, 25.0, 0.50, 6.25, 10.0);
+const HAIKU_45: ModelPricing = ModelPricing::new(1.0, 5.0, 0.10, 1.25, 2.0);
+const HAIKU_35: ModelPricing = ModelPricing::new(0.80, 4.0, 0.08, 1.0, 1.6);
+const HAIKU_3: ModelPricing = ModelPricing::new(0.25, 1.25, 0.03, 0.30, 0.50);
+
+// Gemini has no cache write surcharge; writes are billed as input
+const GEMINI_3_PRO: ModelPricing = ModelPricing::new(2.0, 12.0, 0.20, 2.0, 2.0);
+const GEMINI_25_PRO: ModelPricing = ModelPricing::new(1.25, 10.0, 0.125, 1.25, 1.25);
+const GEMINI_25_FLASH: ModelPricing = ModelPricing::new(0.30, 2.50, 0.03, 0.30, 0.30);
+const GEMINI_FLASH_LITE: ModelPricing = ModelPricing::new(0.10, 0.40, 0.01, 0.10, 0.10);
+const GEMINI_20_FLASH: ModelPricing = ModelPricing::new(0.10, 0.40, 0.025, 0.10, 0.10);
+
+// OpenAI models used by Codex (cached input is the only cache rate)
+const GPT_5: ModelPricing = ModelPricing::new(1.25, 10.0, 0.125, 1.25, 1.25);
+const GPT_5_MINI: ModelPricing = ModelPricing::new(0.25, 2.0, 0.025, 0.25, 0.25);
+const GPT_5_NANO: ModelPricing = ModelPricing::new(0.05, 0.40, 0.005, 0.05, 0.05);
+const O3: ModelPricing = ModelPricing::new(2.0, 8.0, 0.50, 2.0, 2.0);
+const O3_MINI: ModelPricing = ModelPricing::new(1.10, 4.40, 0.55, 1.10, 1.10);
+const O4_MINI: ModelPricing = ModelPricing::new(1.10, 4.40, 0.275, 1.10, 1.10);
+
+/// Pricing used for unknown models and usage without a model
+pub const DEFAULT_PRICING: ModelPricing = SONNET;
+
+const BUILTIN: &[(&str, ModelPricing)] = &[
+    ("claude-opus-4-6", OPUS_45),
+    ("claude-opus-4-5", OPUS_45),
+    ("claude-opus-4", OPUS),
+    ("claude-3-opus", OPUS),
+    ("claude-sonnet-4", SONNET),
+    ("claude-3-7-sonnet", SONNET),
+    ("claude-3-5-sonnet", SONNET),
+    ("claude-haiku-4", HAIKU_45),
+    ("claude-3-5-haiku", HAIKU_35),
+    ("claude-3-haiku", HAIKU_3),
+    ("gemini-3-pro", GEMINI_3_PRO),
+    ("gemini-2.5-pro", GEMINI_25_PRO),
+    ("gemini-2.5-flash", GEMINI_25_FLASH),
+    ("gemini-2.5-flash-lite", GEMINI_FLASH_LITE),
+    ("gemini-2.0-flash", GEMINI_20_FLASH),
+    ("gemini-2.0-flash-lite", GEMINI_FLASH_LITE),
+    ("gpt-5", GPT_5),
+    ("gpt-5-mini", GPT_5_MINI),
+    ("gpt-5-nano", GPT_5_NANO),
+    ("gpt-5.1-codex-mini", GPT_5_MINI),
+    ("o3", O3),
+    ("o3-mini", O3_MINI),
+    ("o4-mini", O4_MINI),
+];
+
+#[derive(Debug, Clone)]
+pub struct PricingTable {
+    /// (model prefix, pricing), overrides included
+    entries: Vec<(String, ModelPricing)>,
+}
+
+impl PricingTable {
+    pub fn builtin() -> Self {
+        Self {
+            entries: BUILTIN
+                .iter()
+                .map(|(prefix, pricing)| (prefix.to_string(), *pricing))
+                .collect(),
+        }
+    }
+
+    /// Built-in table with user overrides applied on top
+    pub fn with_overrides(overrides: &BTreeMap<String, ModelPricing>) -> Self {
+        let mut table = Self::builtin();
+        for (prefix, pricing) in overrides {
+            table.entries.retain(|(p, _)| p != prefix);
+            table.entries.push((prefix.clone(), *pricing));
+        }
+        table
+    }
+
+    /// Pricing for a model, by longest matching prefix
+    pub fn lookup(&self, model: &str) -> ModelPricing {
+        self.entries
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, pricing)| *pricing)
+            .unwrap_or(DEFAULT_PRICING)
+    }
+}
+
+/// Process-wide pricing table, loaded from the config on first use
+pub fn table() -> &'static PricingTable {
+    static TABLE: OnceLock<PricingTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        config::shared()
+            .ok()
+            .and_then(|config| config.pricing.as_ref())
+            .map(PricingTable::with_overrides)
+            .unwrap_or_else(PricingTable::builtin)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_longest_prefix() {
+        let table = PricingTable::builtin();
+        assert_eq!(table.lookup("claude-opus-4-1-20250805"), OPUS);
+        assert_eq!(table.lookup("claude-opus-4-5-20251101"), OPUS_45);
+        assert_eq!(table.lookup("claude-haiku-4-5-20251001"), HAIKU_45);
+        assert_eq!(table.lookup("claude-sonnet-4-5-20250929"), SONNET);
+        assert_eq!(table.lookup("gemini-2.5-flash-lite"), GEMINI_FLASH_LITE);
+        assert_eq!(table.lookup("gemini-2.5-flash"), GEMINI_25_FLASH);
+        assert_eq!(table.lookup("gpt-5-codex"), GPT_5);
+        assert_eq!(table.lookup("gpt-5.1-codex-mini"), GPT_5_MINI);
+        assert_eq!(table.lookup("o3-2025-04-16"), O3);
+        assert_eq!(table.lookup("o3-mini-2025-01-31"), O3_MINI);
+        assert_eq!(table.lookup("some-new-model"), DEFAULT_PRICING);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut overrides = BTreeMap::new();
+        let custom = ModelPricing::new(1.0, 2.0, 0.1, 1.5, 2.5);
+        overrides.insert("claude-opus-4".to_string(), custom);
+        overrides.insert("internal-".to_string(), custom);
+
+        let table = PricingTable::with_overrides(&overrides);
+        assert_eq!(table.lookup("claude-opus-4-1"), custom);
+        assert_eq!(table.lookup("internal-model"), custom);
+        assert_eq!(table.lookup("claude-opus-4-5"), OPUS_45);
+    }
+}
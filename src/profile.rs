@@ -0,0 +1,142 @@
+//! Claude Code profiles: named config directories
+//!
+//! Claude Code keeps settings and transcripts in `CLAUDE_CONFIG_DIR`, or
+//! ~/.claude by default. People with several accounts give each its own
+//! directory and list them under `profiles` in the config; without that,
+//! the single directory Claude would use is the "default" profile.
+//! `--profile` narrows every command to one of them.
+
+use crate::config;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    /// Claude config directory, holding settings.json and projects/
+    pub dir: PathBuf,
+}
+
+impl Profile {
+    pub fn projects_dir(&self) -> PathBuf {
+        self.dir.join("projects")
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.dir.join("settings.json")
+    }
+
+    /// Whether Claude needs CLAUDE_CONFIG_DIR set to use this profile
+    pub fn needs_env(&self) -> bool {
+        Some(self.dir.clone()) != dirs::home_dir().map(|h| h.join(".claude"))
+    }
+}
+
+/// Directory Claude Code uses when nothing else is said
+fn default_dir() -> Option<PathBuf> {
+    match std::env::var("CLAUDE_CONFIG_DIR") {
+        Ok(dir) if !dir.is_empty() => Some(config::expand_home(&dir)),
+        _ => dirs::home_dir().map(|h| h.join(".claude")),
+    }
+}
+
+/// Every known profile, sorted by name
+pub fn all() -> &'static [Profile] {
+    static PROFILES: OnceLock<Vec<Profile>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        let configured = config::shared().map(|c| c.profiles.clone()).unwrap_or_default();
+        if configured.is_empty() {
+            return default_dir()
+                .map(|dir| Profile {
+                    name: DEFAULT_PROFILE.to_string(),
+                    dir,
+                })
+                .into_iter()
+                .collect();
+        }
+        configured
+            .into_iter()
+            .map(|(name, dir)| Profile {
+                dir: config::expand_home(&dir),
+                name,
+            })
+            .collect()
+    })
+}
+
+static SELECTED: OnceLock<Option<&'static Profile>> = OnceLock::new();
+
+/// Narrow all commands to the profile named by `--profile`
+pub fn select(name: Option<&str>) -> Result<()> {
+    let selected = match name {
+        Some(name) => Some(find(name)?),
+        None => None,
+    };
+    let _ = SELECTED.set(selected);
+    Ok(())
+}
+
+fn find(name: &str) -> Result<&'static Profile> {
+    // Without a readable config only the default profile is known, which
+    // would hide the actual problem
+    config::shared()?;
+    all().iter().find(|p| p.name == name).ok_or_else(|| {
+        let known: Vec<&str> = all().iter().map(|p| p.name.as_str()).collect();
+        anyhow::anyhow!("Unknown profile '{}' (known: {})", name, known.join(", "))
+    })
+}
+
+/// The profile chosen with `--profile`, if any
+pub fn selected() -> Option<&'static Profile> {
+    SELECTED.get().copied().flatten()
+}
+
+/// Profiles the current command covers: the selected one, or all of them
+pub fn active() -> Vec<&'static Profile> {
+    match selected() {
+        Some(profile) => vec![profile],
+        None => all().iter().collect(),
+    }
+}
+
+/// Whether profiles need to be told apart in output
+pub fn has_several() -> bool {
+    all().len() > 1
+}
+
+/// Whether `profiles` covers every profile, so usage that belongs to none
+/// of them (e.g. Codex) counts too
+pub fn covers_all(profiles: &[&Profile]) -> bool {
+    profiles.len() == all().len()
+}
+
+/// The one profile a command that writes settings should act on
+pub fn single() -> Result<&'static Profile> {
+    if let Some(profile) = selected() {
+        return Ok(profile);
+    }
+    match all() {
+        [profile] => Ok(profile),
+        profiles => {
+            let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+            anyhow::bail!("Several profiles are configured; choose one with --profile ({})", names.join(", "))
+        }
+    }
+}
+
+/// Profile whose config directory contains `path`, e.g. a transcript
+pub fn for_path(path: &Path) -> Option<&'static Profile> {
+    all()
+        .iter()
+        .filter(|p| path.starts_with(&p.dir))
+        .max_by_key(|p| p.dir.as_os_str().len())
+}
+
+/// Profile of the running Claude process, from its CLAUDE_CONFIG_DIR
+pub fn current() -> Option<&'static Profile> {
+    let dir = default_dir()?;
+    all().iter().find(|p| p.dir == dir)
+}
@@ -0,0 +1,167 @@
+//! Aider
+//!
+//! Aider appends every session to `.aider.chat.history.md` in the repo root.
+//! Tool output is quoted with `> `, including the model banner and a
+//! `Tokens: 2.5k sent, 150 received. Cost: ...` report after each reply.
+
+use super::Provider;
+use crate::cost::{LineParser, ModelUsage, SessionRef, TokenUsage, UsageFormat, UNKNOWN_MODEL};
+use crate::hooks::{self, HookConfig};
+use ratatui::style::Color;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const HISTORY_FILE: &str = ".aider.chat.history.md";
+
+pub struct Aider;
+
+impl Provider for Aider {
+    fn name(&self) -> &'static str {
+        "aider"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Aider"
+    }
+
+    fn color(&self) -> Color {
+        Color::Yellow
+    }
+
+    fn is_running(&self, command: &str) -> bool {
+        // Panes running it through Python are named by the script (see `mux`),
+        // so a plain interpreter or another script is not Aider
+        command == "aider"
+    }
+
+    fn hooks(&self) -> Option<&'static dyn HookConfig> {
+        Some(&hooks::AiderConfig)
+    }
+
+    fn locate_transcript(&self, session: &SessionRef) -> Option<PathBuf> {
+        if let Some(ref path) = session.transcript_path {
+            let path = PathBuf::from(path);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        // The history lives in the git root, which may be above the pane's cwd
+        Path::new(&session.working_dir)
+            .ancestors()
+            .map(|dir| dir.join(HISTORY_FILE))
+            .find(|p| p.is_file())
+    }
+
+    fn usage_format(&self) -> Option<UsageFormat> {
+        Some(UsageFormat::Lines(Arc::new(line_parser)))
+    }
+}
+
+/// Tracks the model banner and restarts totals at each new session header
+#[derive(Debug, Default)]
+struct HistoryState {
+    model: Option<String>,
+}
+
+impl LineParser for HistoryState {
+    fn add_line(&mut self, usage: &mut TokenUsage, line: &str) {
+        let line = line.trim_end();
+
+        // The file accumulates every session; only the latest one counts
+        if line.starts_with("# aider chat started at") {
+            *usage = TokenUsage::default();
+            self.model = None;
+            return;
+        }
+
+        let rest = match line.strip_prefix("> ") {
+            Some(r) => r,
+            None => return,
+        };
+
+        let banner = rest
+            .strip_prefix("Main model: ")
+            .or_else(|| rest.strip_prefix("Models: "))
+            .or_else(|| rest.strip_prefix("Model: "));
+        if let Some(banner) = banner {
+            // e.g. "anthropic/claude-sonnet-4-20250514 with diff edit format"
+            self.model = banner
+                .split_whitespace()
+                .next()
+                .map(|m| m.rsplit('/').next().unwrap_or(m).to_string());
+        } else if let Some(report) = rest.strip_prefix("Tokens: ") {
+            if let Some(report_usage) = parse_tokens_report(report) {
+                let model = self.model.as_deref().unwrap_or(UNKNOWN_MODEL);
+                usage.add(model, &report_usage);
+            }
+        }
+    }
+}
+
+pub fn line_parser() -> Box<dyn LineParser> {
+    Box::<HistoryState>::default()
+}
+
+/// Parse "12k sent, 8.2k cache write, 3k cache hit, 350 received. Cost: ..."
+fn parse_tokens_report(report: &str) -> Option<ModelUsage> {
+    let counts = report.split(" Cost:").next()?.trim().trim_end_matches('.');
+
+    let mut usage = ModelUsage::default();
+    for part in counts.split(", ") {
+        let (count, label) = part.trim().split_once(' ')?;
+        let count = parse_count(count)?;
+        match label {
+            "sent" => usage.input_tokens += count,
+            "received" => usage.output_tokens += count,
+            "cache write" => usage.cache_write_5m_tokens += count,
+            "cache hit" => usage.cache_read_tokens += count,
+            _ => {}
+        }
+    }
+    Some(usage)
+}
+
+/// Parse Aider's rounded counts ("350", "2.5k", "1.2M")
+fn parse_count(count: &str) -> Option<u64> {
+    let (number, scale) = if let Some(n) = count.strip_suffix('k') {
+        (n, 1_000.0)
+    } else if let Some(n) = count.strip_suffix('M') {
+        (n, 1_000_000.0)
+    } else {
+        (count, 1.0)
+    };
+    number.parse::<f64>().ok().map(|n| (n * scale).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_usage_latest_session() {
+        let history = "# aider chat started at 2026-10-17 09:00:00\n\
+            > Main model: gpt-4o with diff edit format\n\
+            > Tokens: 9k sent, 1k received. Cost: $0.03 message, $0.03 session.\n\
+            \n\
+            # aider chat started at 2026-10-18 10:00:00\n\
+            \n\
+            > Main model: anthropic/claude-sonnet-4-20250514 with diff edit format, infinite output\n\
+            #### fix the tests\n\
+            > Tokens: 2.5k sent, 1.2k cache write, 3k cache hit, 150 received. Cost: $0.01 message, $0.01 session.\n\
+            > Tokens: 500 sent, 50 received. Cost: $0.002 message, $0.012 session.\n";
+
+        let mut parser = line_parser();
+        let mut usage = TokenUsage::default();
+        for line in history.lines() {
+            parser.add_line(&mut usage, line);
+        }
+
+        assert_eq!(usage.input_tokens, 3_000);
+        assert_eq!(usage.output_tokens, 200);
+        assert_eq!(usage.cache_read_tokens, 3_000);
+        assert_eq!(usage.cache_write_tokens, 1_200);
+        assert_eq!(usage.by_model.len(), 1);
+        assert!(usage.by_model.contains_key("claude-sonnet-4-20250514"));
+    }
+}
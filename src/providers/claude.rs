@@ -0,0 +1,383 @@
+//! Claude Code
+//!
+//! Sessions are JSONL transcripts under ~/.claude/projects/{path_hash}/*.jsonl
+//! (or the projects/ of each profile's config dir), with a `message.usage`
+//! object on each assistant line.
+
+use super::Provider;
+use crate::cost::{line_timestamp, LineParser, ModelUsage, SessionRef, TokenUsage, UsageEntry, UsageFormat, UNKNOWN_MODEL};
+use crate::hooks::{self, HookConfig};
+use crate::usage_index::IndexKind;
+use crate::profile;
+use ratatui::style::Color;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Lines of an `agent-*.jsonl` read looking for its parent `sessionId`
+const FIRST_LINES_CHECKED: usize = 20;
+
+pub struct Claude;
+
+impl Provider for Claude {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Code"
+    }
+
+    fn color(&self) -> Color {
+        Color::Magenta
+    }
+
+    fn is_running(&self, command: &str) -> bool {
+        // Claude Code shows as version number (e.g., "2.1.6", "2.1.7")
+        // or "claude" or "node" depending on how it was launched
+        is_version_string(command) || command == "claude" || command == "node"
+    }
+
+    fn hooks(&self) -> Option<&'static dyn HookConfig> {
+        Some(&hooks::ClaudeSettings)
+    }
+
+    fn locate_transcript(&self, session: &SessionRef) -> Option<PathBuf> {
+        locate_transcript(session)
+    }
+
+    fn usage_format(&self) -> Option<UsageFormat> {
+        Some(UsageFormat::Lines(Arc::new(line_parser)))
+    }
+
+    fn index_kind(&self) -> Option<IndexKind> {
+        Some(IndexKind::Claude)
+    }
+
+    fn subagent_transcripts(&self, transcript: &Path) -> Vec<PathBuf> {
+        subagent_transcripts(transcript)
+    }
+}
+
+/// Check if string looks like a version number (e.g., "2.1.6")
+fn is_version_string(s: &str) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+    // Version strings: digits and dots, starting with a digit
+    s.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false)
+        && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Projects directories of the active profiles
+fn projects_dirs() -> Vec<PathBuf> {
+    profile::active().iter().map(|p| p.projects_dir()).collect()
+}
+
+/// Hash a path the way Claude does (replace / and _ with -)
+pub fn hash_path(path: &str) -> String {
+    path.replace(['/', '_'], "-")
+}
+
+/// Find JSONL session files for a given working directory
+fn find_session_files(working_dir: &str) -> Vec<PathBuf> {
+    let path_hash = hash_path(working_dir);
+
+    projects_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&path_hash))
+        .filter_map(|session_dir| fs::read_dir(session_dir).ok())
+        .flat_map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
+                .filter(|p| !is_subagent_transcript(p))
+        })
+        .collect()
+}
+
+/// Usage recorded by a single transcript JSONL line, with its time and ids
+pub fn parse_usage_entry(line: &str) -> Option<UsageEntry> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let json = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    usage_entry(&json)
+}
+
+/// Usage recorded by an already parsed transcript line
+pub fn usage_entry(json: &serde_json::Value) -> Option<UsageEntry> {
+    let (model, usage) = message_usage(json)?;
+    let text = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+
+    // Streamed responses repeat one message's usage on several lines
+    let message_id = text(json.get("message").and_then(|m| m.get("id")));
+    let request_id = text(json.get("requestId"));
+    let id = match (message_id, request_id) {
+        (None, None) => None,
+        (message, request) => Some(format!("{}:{}", message.unwrap_or_default(), request.unwrap_or_default())),
+    };
+
+    Some(UsageEntry {
+        timestamp: line_timestamp(json),
+        model,
+        usage,
+        id,
+        sidechain: json.get("isSidechain").and_then(|v| v.as_bool()).unwrap_or(false),
+        session_id: text(json.get("sessionId")),
+        cwd: text(json.get("cwd")),
+    })
+}
+
+fn message_usage(json: &serde_json::Value) -> Option<(String, ModelUsage)> {
+    // Usage is nested: {"message": {"model": "...", "usage": {...}}}
+    let message = json.get("message")?;
+    let usage_obj = message.get("usage")?;
+    let get = |obj: &serde_json::Value, key: &str| obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+
+    let cache_write_total = get(usage_obj, "cache_creation_input_tokens");
+    let (cache_write_5m, cache_write_1h) = match usage_obj.get("cache_creation") {
+        Some(split) => (
+            get(split, "ephemeral_5m_input_tokens"),
+            get(split, "ephemeral_1h_input_tokens"),
+        ),
+        None => (cache_write_total, 0),
+    };
+
+    let model = message
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or(UNKNOWN_MODEL)
+        .to_string();
+
+    Some((
+        model,
+        ModelUsage {
+            input_tokens: get(usage_obj, "input_tokens"),
+            output_tokens: get(usage_obj, "output_tokens"),
+            cache_read_tokens: get(usage_obj, "cache_read_input_tokens"),
+            cache_write_5m_tokens: cache_write_5m,
+            cache_write_1h_tokens: cache_write_1h,
+        },
+    ))
+}
+
+/// Claude JSONL: each line carries its own usage, counted once per message
+#[derive(Default)]
+struct ClaudeLines {
+    seen: HashSet<String>,
+}
+
+impl LineParser for ClaudeLines {
+    fn add_line(&mut self, usage: &mut TokenUsage, line: &str) {
+        if let Some(entry) = parse_usage_entry(line) {
+            if let Some(id) = entry.id {
+                if !self.seen.insert(id) {
+                    return;
+                }
+            }
+            usage.add(&entry.model, &entry.usage);
+        }
+    }
+}
+
+pub fn line_parser() -> Box<dyn LineParser> {
+    Box::<ClaudeLines>::default()
+}
+
+/// Whether a transcript was written by a subagent: either under
+/// `<session>/subagents/`, or an `agent-*.jsonl` next to the sessions
+pub fn is_subagent_transcript(path: &Path) -> bool {
+    let in_subagents = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n == "subagents")
+        .unwrap_or(false);
+    let agent_file = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.starts_with("agent-"))
+        .unwrap_or(false);
+    in_subagents || agent_file
+}
+
+/// Session a subagent transcript works for
+///
+/// Files under `<session>/subagents/` are named by their directory; older
+/// `agent-*.jsonl` files record it as `sessionId` on each line.
+pub fn subagent_parent(path: &Path) -> Option<String> {
+    let parent = path.parent()?;
+    if parent.file_name()? == "subagents" {
+        return parent.parent()?.file_name()?.to_str().map(str::to_string);
+    }
+    let file = fs::File::open(path).ok()?;
+    BufReader::new(file)
+        .lines()
+        .take(FIRST_LINES_CHECKED)
+        .map_while(Result::ok)
+        .find_map(|line| {
+            let json: serde_json::Value = serde_json::from_str(&line).ok()?;
+            json.get("sessionId")?.as_str().map(str::to_string)
+        })
+}
+
+/// Subagent transcripts that belong to a session transcript
+pub fn subagent_transcripts(transcript: &Path) -> Vec<PathBuf> {
+    let (dir, session_id) = match (transcript.parent(), transcript.file_stem().and_then(|s| s.to_str())) {
+        (Some(dir), Some(id)) => (dir, id),
+        _ => return Vec::new(),
+    };
+    let jsonl_files = |dir: PathBuf| {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
+    };
+
+    let mut files: Vec<PathBuf> = jsonl_files(dir.join(session_id).join("subagents")).collect();
+    files.extend(
+        jsonl_files(dir.to_path_buf())
+            .filter(|p| is_subagent_transcript(p))
+            .filter(|p| subagent_parent(p).as_deref() == Some(session_id)),
+    );
+    files
+}
+
+/// Find the transcript for a Claude session
+///
+/// Prefers the hook-published transcript path, then a `<session_id>.jsonl`
+/// in any project dir, and finally falls back to the most recently modified
+/// transcript for the working directory.
+pub fn locate_transcript(session: &SessionRef) -> Option<PathBuf> {
+    if let Some(ref path) = session.transcript_path {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    if let Some(ref id) = session.session_id {
+        if let Some(path) = find_session_by_id(&session.working_dir, id) {
+            return Some(path);
+        }
+    }
+
+    most_recent_session_file(&session.working_dir)
+}
+
+/// Look up `<session_id>.jsonl` in every profile, checking the working
+/// directory's project first
+fn find_session_by_id(working_dir: &str, session_id: &str) -> Option<PathBuf> {
+    let dirs = projects_dirs();
+    let file_name = format!("{}.jsonl", session_id);
+
+    let direct = dirs
+        .iter()
+        .map(|dir| dir.join(hash_path(working_dir)).join(&file_name))
+        .find(|p| p.is_file());
+    if direct.is_some() {
+        return direct;
+    }
+
+    // The agent may have been started elsewhere and cd'ed since
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join(&file_name))
+        .find(|p| p.is_file())
+}
+
+/// Most recently modified session file for a working directory
+fn most_recent_session_file(working_dir: &str) -> Option<PathBuf> {
+    find_session_files(working_dir)
+        .into_iter()
+        .filter_map(|p| {
+            fs::metadata(&p)
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .map(|t| (p, t))
+        })
+        .max_by_key(|(_, t)| *t)
+        .map(|(p, _)| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_usage_line_by_model() {
+        let line = r#"{"message":{"model":"claude-opus-4-1-20250805","usage":{"input_tokens":1000000,"output_tokens":0,"cache_read_input_tokens":0,"cache_creation_input_tokens":3000000,"cache_creation":{"ephemeral_5m_input_tokens":1000000,"ephemeral_1h_input_tokens":2000000}}}}"#;
+        let entry = parse_usage_entry(line).unwrap();
+        let (model, parsed) = (entry.model, entry.usage);
+        assert_eq!(model, "claude-opus-4-1-20250805");
+        assert_eq!(parsed.cache_write_5m_tokens, 1_000_000);
+        assert_eq!(parsed.cache_write_1h_tokens, 2_000_000);
+
+        let mut usage = TokenUsage::default();
+        usage.add(&model, &parsed);
+        usage.add("claude-haiku-4-5-20251001", &ModelUsage {
+            output_tokens: 1_000_000,
+            ..Default::default()
+        });
+        assert_eq!(usage.cache_write_tokens, 3_000_000);
+        assert_eq!(usage.by_model.len(), 2);
+
+        // Opus: $15 input + $18.75 5m writes + 2 * $30 1h writes; Haiku 4.5: $5 output
+        let expected = 15.0 + 18.75 + 60.0 + 5.0;
+        assert!((usage.cost_usd() - expected).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_streamed_message_counted_once() {
+        let line = |msg: &str, req: &str, sidechain: bool| {
+            format!(
+                r#"{{"sessionId":"s1","isSidechain":{},"requestId":"{}","message":{{"id":"{}","model":"claude-sonnet-4-5","usage":{{"input_tokens":10,"output_tokens":5}}}}}}"#,
+                sidechain, req, msg
+            )
+        };
+        let entry = parse_usage_entry(&line("msg_1", "req_1", true)).unwrap();
+        assert!(entry.sidechain);
+        assert_eq!(entry.session_id.as_deref(), Some("s1"));
+
+        let mut parser = line_parser();
+        let mut usage = TokenUsage::default();
+        for text in [line("msg_1", "req_1", false), line("msg_1", "req_1", false), line("msg_2", "req_2", false)] {
+            parser.add_line(&mut usage, &text);
+        }
+        assert_eq!(usage.input_tokens, 20);
+        assert_eq!(usage.output_tokens, 10);
+    }
+
+    #[test]
+    fn test_locate_prefers_published_transcript() {
+        let path = std::env::temp_dir().join(format!("coder-tools-locate-{}.jsonl", std::process::id()));
+        fs::write(&path, "").unwrap();
+
+        let session = SessionRef {
+            working_dir: "/nonexistent/project".to_string(),
+            session_id: Some("abc".to_string()),
+            transcript_path: Some(path.to_string_lossy().to_string()),
+        };
+        assert_eq!(locate_transcript(&session), Some(path.clone()));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_version_string() {
+        assert!(is_version_string("2.1.6"));
+        assert!(is_version_string("2.1.7"));
+        assert!(is_version_string("1.0.0"));
+        assert!(!is_version_string("fish"));
+        assert!(!is_version_string("node"));
+        assert!(!is_version_string(""));
+    }
+}
@@ -0,0 +1,330 @@
+//! Token usage for Codex CLI sessions
+//!
+//! Codex writes one rollout JSONL per session under
+//! ~/.codex/sessions/YYYY/MM/DD/rollout-<timestamp>-<session_id>.jsonl.
+//! The first line is a `session_meta` record with the session cwd, and
+//! `token_count` events carry cumulative usage for the session.
+
+use super::Provider;
+use crate::cost::{line_timestamp, LineParser, ModelUsage, SessionRef, TokenUsage, UsageEntry, UsageFormat, UNKNOWN_MODEL};
+use crate::hooks::{self, HookConfig};
+use crate::usage_index::IndexKind;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Rollouts checked for a matching cwd before giving up
+const MAX_ROLLOUTS_SCANNED: usize = 50;
+
+pub struct Codex;
+
+impl Provider for Codex {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Codex CLI"
+    }
+
+    fn color(&self) -> Color {
+        Color::Green
+    }
+
+    fn is_running(&self, command: &str) -> bool {
+        // Codex binary may show as "codex" or "codex-aarch64-..." etc.
+        command.starts_with("codex") || command == "node"
+    }
+
+    fn hooks(&self) -> Option<&'static dyn HookConfig> {
+        Some(&hooks::CodexConfig)
+    }
+
+    fn locate_transcript(&self, session: &SessionRef) -> Option<PathBuf> {
+        locate_transcript(session)
+    }
+
+    fn usage_format(&self) -> Option<UsageFormat> {
+        Some(UsageFormat::Lines(Arc::new(line_parser)))
+    }
+
+    fn index_kind(&self) -> Option<IndexKind> {
+        Some(IndexKind::Codex)
+    }
+}
+
+pub fn sessions_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".codex").join("sessions"))
+}
+
+/// All rollout files with their modification times, newest first
+pub fn rollout_files() -> Vec<(PathBuf, SystemTime)> {
+    let mut files = Vec::new();
+    if let Some(dir) = sessions_dir() {
+        collect_rollouts(&dir, &mut files);
+    }
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    files
+}
+
+fn collect_rollouts(dir: &Path, files: &mut Vec<(PathBuf, SystemTime)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rollouts(&path, files);
+        } else if is_rollout(&path) {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                files.push((path, modified));
+            }
+        }
+    }
+}
+
+fn is_rollout(path: &Path) -> bool {
+    path.extension().map(|e| e == "jsonl").unwrap_or(false)
+        && path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with("rollout-"))
+            .unwrap_or(false)
+}
+
+/// Identity of a rollout, from its `session_meta` line
+#[derive(Debug, Clone, Default)]
+pub struct SessionMeta {
+    pub id: String,
+    pub cwd: String,
+}
+
+pub fn read_session_meta(path: &Path) -> Option<SessionMeta> {
+    let file = fs::File::open(path).ok()?;
+    let first = BufReader::new(file).lines().next()?.ok()?;
+    let json: serde_json::Value = serde_json::from_str(&first).ok()?;
+    if json.get("type")?.as_str()? != "session_meta" {
+        return None;
+    }
+    let payload = json.get("payload")?;
+    Some(SessionMeta {
+        id: payload.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        cwd: payload.get("cwd").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+    })
+}
+
+/// Find the active rollout for a Codex session
+///
+/// Prefers a published transcript path, then a rollout named after the
+/// session id, then the most recent rollout whose cwd is the pane's cwd.
+pub fn locate_transcript(session: &SessionRef) -> Option<PathBuf> {
+    if let Some(ref path) = session.transcript_path {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let files = rollout_files();
+
+    if let Some(ref id) = session.session_id {
+        let suffix = format!("{}.jsonl", id);
+        if let Some((path, _)) = files.iter().find(|(p, _)| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.ends_with(&suffix))
+                .unwrap_or(false)
+        }) {
+            return Some(path.clone());
+        }
+    }
+
+    files
+        .into_iter()
+        .take(MAX_ROLLOUTS_SCANNED)
+        .find(|(p, _)| {
+            read_session_meta(p)
+                .map(|meta| meta.cwd == session.working_dir)
+                .unwrap_or(false)
+        })
+        .map(|(p, _)| p)
+}
+
+/// Parser state carried across lines of one rollout
+///
+/// Usage is cumulative per session, so each `token_count` event is turned
+/// into a delta against the previous total and attributed to the model
+/// from the latest `turn_context`. Repeated events add nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolloutState {
+    model: Option<String>,
+    last_total: ModelUsage,
+}
+
+impl RolloutState {
+    /// Usage added by one line, attributed to the current model
+    fn delta(&mut self, json: &serde_json::Value) -> Option<(String, ModelUsage)> {
+        let payload = json.get("payload")?;
+
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("turn_context") => {
+                if let Some(model) = payload.get("model").and_then(|m| m.as_str()) {
+                    self.model = Some(model.to_string());
+                }
+                None
+            }
+            Some("event_msg") if payload.get("type").and_then(|t| t.as_str()) == Some("token_count") => {
+                let total = parse_token_usage(payload.get("info")?.get("total_token_usage")?);
+                let delta = ModelUsage {
+                    input_tokens: total.input_tokens.saturating_sub(self.last_total.input_tokens),
+                    output_tokens: total.output_tokens.saturating_sub(self.last_total.output_tokens),
+                    cache_read_tokens: total
+                        .cache_read_tokens
+                        .saturating_sub(self.last_total.cache_read_tokens),
+                    cache_write_5m_tokens: 0,
+                    cache_write_1h_tokens: 0,
+                };
+                self.last_total = total;
+
+                if delta == ModelUsage::default() {
+                    return None;
+                }
+                let model = self.model.as_deref().unwrap_or(UNKNOWN_MODEL);
+                Some((model.to_string(), delta))
+            }
+            _ => None,
+        }
+    }
+
+    /// Usage of one line as of its `token_count` event, with the event's time
+    pub fn entry(&mut self, json: &serde_json::Value) -> Option<UsageEntry> {
+        let (model, usage) = self.delta(json)?;
+        Some(UsageEntry {
+            timestamp: line_timestamp(json),
+            model,
+            usage,
+            ..Default::default()
+        })
+    }
+}
+
+impl LineParser for RolloutState {
+    fn add_line(&mut self, usage: &mut TokenUsage, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let json: serde_json::Value = match serde_json::from_str(line) {
+            Ok(j) => j,
+            Err(_) => return,
+        };
+        if let Some((model, delta)) = self.delta(&json) {
+            usage.add(&model, &delta);
+        }
+    }
+}
+
+/// Convert a Codex usage object; `input_tokens` includes cached input
+fn parse_token_usage(obj: &serde_json::Value) -> ModelUsage {
+    let get = |key: &str| obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let cached = get("cached_input_tokens");
+    ModelUsage {
+        input_tokens: get("input_tokens").saturating_sub(cached),
+        // Reasoning tokens are already part of output_tokens
+        output_tokens: get("output_tokens"),
+        cache_read_tokens: cached,
+        cache_write_5m_tokens: 0,
+        cache_write_1h_tokens: 0,
+    }
+}
+
+pub fn line_parser() -> Box<dyn LineParser> {
+    Box::<RolloutState>::default()
+}
+
+/// Conversation messages of a rollout as (role, text), oldest first
+///
+/// Injected context (instructions, environment) is wrapped in tags and skipped.
+pub fn rollout_messages(path: &Path) -> Vec<(String, String)> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let json: serde_json::Value = serde_json::from_str(line).ok()?;
+            if json.get("type")?.as_str()? != "response_item" {
+                return None;
+            }
+            let payload = json.get("payload")?;
+            if payload.get("type")?.as_str()? != "message" {
+                return None;
+            }
+            let role = payload.get("role")?.as_str()?.to_string();
+            let text = payload
+                .get("content")?
+                .as_array()?
+                .iter()
+                .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if text.trim_start().starts_with('<') {
+                return None;
+            }
+            Some((role, text))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLOUT: &str = r#"{"timestamp":"2026-01-01T10:00:00Z","type":"session_meta","payload":{"id":"0199-abc","cwd":"/home/user/proj"}}
+{"timestamp":"2026-01-01T10:00:01Z","type":"turn_context","payload":{"cwd":"/home/user/proj","model":"gpt-5-codex"}}
+{"timestamp":"2026-01-01T10:00:05Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":1000,"cached_input_tokens":400,"output_tokens":100,"reasoning_output_tokens":50,"total_tokens":1100}}}}
+{"timestamp":"2026-01-01T10:00:06Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":1000,"cached_input_tokens":400,"output_tokens":100,"reasoning_output_tokens":50,"total_tokens":1100}}}}
+{"timestamp":"2026-01-01T10:01:00Z","type":"turn_context","payload":{"cwd":"/home/user/proj","model":"gpt-5-mini"}}
+{"timestamp":"2026-01-01T10:01:05Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":1500,"cached_input_tokens":900,"output_tokens":160,"reasoning_output_tokens":50,"total_tokens":1660}}}}
+"#;
+
+    #[test]
+    fn test_rollout_usage() {
+        let path = std::env::temp_dir().join(format!("rollout-test-{}.jsonl", std::process::id()));
+        fs::write(&path, ROLLOUT).unwrap();
+
+        let meta = read_session_meta(&path).unwrap();
+        assert_eq!(meta.id, "0199-abc");
+        assert_eq!(meta.cwd, "/home/user/proj");
+
+        let usage = UsageFormat::Lines(Arc::new(line_parser)).parse_file(&path);
+        // Duplicate token_count events are not double counted
+        assert_eq!(usage.input_tokens, 600);
+        assert_eq!(usage.cache_read_tokens, 900);
+        assert_eq!(usage.output_tokens, 160);
+        assert_eq!(usage.by_model["gpt-5-codex"].output_tokens, 100);
+        assert_eq!(usage.by_model["gpt-5-mini"].output_tokens, 60);
+
+        // The same deltas, each at the time of its event
+        let mut state = RolloutState::default();
+        let entries: Vec<_> = ROLLOUT
+            .lines()
+            .filter_map(|line| state.entry(&serde_json::from_str(line).ok()?))
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].model, "gpt-5-mini");
+        assert_eq!(entries[1].usage.input_tokens, 0);
+        assert_eq!(
+            entries[1].timestamp.map(|t| t.to_rfc3339()),
+            Some("2026-01-01T10:01:05+00:00".to_string())
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! Agents declared in the config file
+//!
+//! Agents without hooks are recognised by `pane_current_command`, and their
+//! status by matching regexes against the pane's visible text. Transcripts,
+//! if any, are JSONL files whose token fields are named by dotted paths.
+
+use super::Provider;
+use crate::config;
+use crate::cost::{LineParser, ModelUsage, SessionRef, TokenUsage, UsageFormat, UNKNOWN_MODEL};
+use crate::mux::{self, Pane};
+use anyhow::{Context, Result};
+use ratatui::style::Color;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// An agent entry under `agents` in the config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Badge label, also accepted as a hook-published @agent_provider
+    pub name: String,
+    /// Badge color: a name ("cyan"), a 256-color index or "#rrggbb"
+    pub color: Option<String>,
+    /// Regexes for `pane_current_command`
+    pub commands: Vec<String>,
+    /// Regexes matched against the pane's visible text
+    pub status: Option<StatusPatterns>,
+    /// Where transcripts live and which fields hold token counts
+    pub transcript: Option<TranscriptConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusPatterns {
+    pub working: Option<String>,
+    pub waiting: Option<String>,
+    pub permission: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptConfig {
+    /// Glob for transcript files (supports ~/); the newest match is used
+    pub glob: String,
+    /// Dotted JSON paths within each line, e.g. "message.usage.input_tokens"
+    pub model: Option<String>,
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub cache_read: Option<String>,
+    pub cache_write: Option<String>,
+}
+
+pub struct CustomAgent {
+    name: &'static str,
+    color: Color,
+    commands: Vec<Regex>,
+    /// Checked in order; the first match decides the status
+    status: Vec<(&'static str, Regex)>,
+    transcript: Option<Arc<TranscriptConfig>>,
+}
+
+impl CustomAgent {
+    pub fn from_config(config: &AgentConfig) -> Result<Self> {
+        if config.name.trim().is_empty() {
+            anyhow::bail!("(unnamed): every agent needs a name");
+        }
+        let name = config.name.trim();
+
+        let color = match config.color {
+            Some(ref c) => Color::from_str(c).map_err(|_| anyhow::anyhow!("{}: unknown color {:?}", name, c))?,
+            None => Color::DarkGray,
+        };
+
+        let compile = |pattern: &str| {
+            Regex::new(pattern).with_context(|| format!("{}: invalid pattern {:?}", name, pattern))
+        };
+
+        let commands = config
+            .commands
+            .iter()
+            .map(|p| compile(p))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Permission outranks waiting, which outranks working
+        let mut status = Vec::new();
+        if let Some(ref patterns) = config.status {
+            for (label, pattern) in [
+                ("permission", &patterns.permission),
+                ("waiting", &patterns.waiting),
+                ("working", &patterns.working),
+            ] {
+                if let Some(pattern) = pattern {
+                    status.push((label, compile(pattern)?));
+                }
+            }
+        }
+
+        Ok(Self {
+            // Lives for the whole process, like the built-in providers
+            name: Box::leak(name.to_string().into_boxed_str()),
+            color,
+            commands,
+            status,
+            transcript: config.transcript.clone().map(Arc::new),
+        })
+    }
+
+    /// Status published for `text`, from the first matching pattern
+    fn status_of(&self, text: &str) -> Option<&'static str> {
+        self.status
+            .iter()
+            .find(|(_, re)| re.is_match(text))
+            .map(|(label, _)| *label)
+    }
+
+    /// Fill in provider and status for a pane, as hooks would have
+    ///
+    /// Without a matching pattern the published @agent_status is kept, and
+    /// with none at all the agent is assumed to be working.
+    pub fn detect(&self, pane: &mut Pane) {
+        pane.agent_provider = Some(self.name.to_string());
+
+        if !self.status.is_empty() {
+            if let Ok(text) = mux::active().capture(pane, 0) {
+                if let Some(status) = self.status_of(text.trim_end()) {
+                    pane.agent_status = Some(status.to_string());
+                }
+            }
+        }
+        if pane.agent_status.is_none() {
+            pane.agent_status = Some("working".to_string());
+        }
+    }
+}
+
+impl Provider for CustomAgent {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn is_running(&self, command: &str) -> bool {
+        self.commands.iter().any(|re| re.is_match(command))
+    }
+
+    fn locate_transcript(&self, session: &SessionRef) -> Option<PathBuf> {
+        if let Some(ref path) = session.transcript_path {
+            let path = PathBuf::from(path);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        let pattern = config::expand_home(&self.transcript.as_ref()?.glob);
+        let mut files: Vec<(PathBuf, std::time::SystemTime)> = glob::glob(&pattern.to_string_lossy())
+            .ok()?
+            .filter_map(|p| p.ok())
+            .filter_map(|p| {
+                let modified = fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+                Some((p, modified))
+            })
+            .collect();
+        files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+
+        // A file named after the published session id beats the newest one
+        if let Some(ref id) = session.session_id {
+            if let Some((path, _)) = files.iter().find(|(p, _)| p.to_string_lossy().contains(id.as_str())) {
+                return Some(path.clone());
+            }
+        }
+        files.into_iter().next().map(|(p, _)| p)
+    }
+
+    fn usage_format(&self) -> Option<UsageFormat> {
+        let fields = Arc::clone(self.transcript.as_ref()?);
+        Some(UsageFormat::Lines(Arc::new(move || {
+            Box::new(JsonLines {
+                fields: Arc::clone(&fields),
+            })
+        })))
+    }
+}
+
+/// JSONL transcript with token counts at configured paths
+struct JsonLines {
+    fields: Arc<TranscriptConfig>,
+}
+
+impl LineParser for JsonLines {
+    fn add_line(&mut self, usage: &mut TokenUsage, line: &str) {
+        let json: serde_json::Value = match serde_json::from_str(line) {
+            Ok(j) => j,
+            Err(_) => return,
+        };
+
+        let count = |path: &Option<String>| {
+            path.as_deref()
+                .and_then(|p| lookup(&json, p))
+                .and_then(|v| v.as_u64())
+        };
+        let counts = [
+            count(&self.fields.input),
+            count(&self.fields.output),
+            count(&self.fields.cache_read),
+            count(&self.fields.cache_write),
+        ];
+        if counts.iter().all(|c| c.is_none()) {
+            return;
+        }
+
+        let model = self
+            .fields
+            .model
+            .as_deref()
+            .and_then(|p| lookup(&json, p))
+            .and_then(|v| v.as_str())
+            .unwrap_or(UNKNOWN_MODEL);
+        let [input, output, cache_read, cache_write] = counts.map(|c| c.unwrap_or(0));
+
+        usage.add(
+            model,
+            &ModelUsage {
+                input_tokens: input,
+                output_tokens: output,
+                cache_read_tokens: cache_read,
+                cache_write_5m_tokens: cache_write,
+                cache_write_1h_tokens: 0,
+            },
+        );
+    }
+}
+
+/// Follow a dotted path ("a.b.0.c") through objects and arrays
+fn lookup<'a>(json: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(json, |value, key| match value {
+        serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_agent_from_config() {
+        let config: AgentConfig = serde_json::from_str(
+            r#"{
+                "name": "acme",
+                "color": "cyan",
+                "commands": ["^acme(-cli)?$"],
+                "status": {"waiting": "(?m)^> $", "permission": "Allow\\?"},
+                "transcript": {"glob": "~/.acme/*.jsonl", "model": "model", "input": "usage.in", "output": "usage.out"}
+            }"#,
+        )
+        .unwrap();
+        let agent = CustomAgent::from_config(&config).unwrap();
+
+        assert_eq!(agent.color(), Color::Cyan);
+        assert!(agent.is_running("acme-cli"));
+        assert!(!agent.is_running("acmes"));
+        assert_eq!(agent.status_of("Run tests?\nAllow?\n> "), Some("permission"));
+        assert_eq!(agent.status_of("done\n> "), Some("waiting"));
+        assert_eq!(agent.status_of("thinking"), None);
+
+        let format = agent.usage_format().unwrap();
+        let UsageFormat::Lines(new_parser) = format else {
+            panic!("expected a line format");
+        };
+        let mut parser = new_parser();
+        let mut usage = TokenUsage::default();
+        parser.add_line(&mut usage, r#"{"model":"acme-1","usage":{"in":100,"out":20}}"#);
+        parser.add_line(&mut usage, r#"{"type":"status"}"#);
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.by_model["acme-1"].output_tokens, 20);
+
+        assert!(CustomAgent::from_config(&AgentConfig {
+            name: "broken".to_string(),
+            commands: vec!["(".to_string()],
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Token usage for Gemini CLI sessions
+//!
+//! Gemini CLI records chats as JSON under
+//! ~/.gemini/tmp/{sha256(project_root)}/chats/session-*.json, with a
+//! `tokens` object on each model message.
+
+use super::Provider;
+use crate::cost::{ModelUsage, SessionRef, TokenUsage, UsageFormat, UNKNOWN_MODEL};
+use crate::hooks::{self, HookConfig};
+use ratatui::style::Color;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct Gemini;
+
+impl Provider for Gemini {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Gemini CLI"
+    }
+
+    fn color(&self) -> Color {
+        Color::Blue
+    }
+
+    fn is_running(&self, command: &str) -> bool {
+        command == "gemini" || command == "node"
+    }
+
+    fn hooks(&self) -> Option<&'static dyn HookConfig> {
+        Some(&hooks::GeminiSettings)
+    }
+
+    fn locate_transcript(&self, session: &SessionRef) -> Option<PathBuf> {
+        locate_transcript(session)
+    }
+
+    fn usage_format(&self) -> Option<UsageFormat> {
+        // Chats are single JSON documents rewritten on every message
+        Some(UsageFormat::Snapshot(parse_chat_tokens))
+    }
+}
+
+fn gemini_tmp_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".gemini").join("tmp"))
+}
+
+/// Hash a project root the way Gemini CLI does (hex sha256 of the path)
+fn project_hash(path: &str) -> String {
+    let digest = Sha256::digest(path.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn chat_files(chats_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(chats_dir)
+        .ok()
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.extension().map(|e| e == "json").unwrap_or(false)
+                        && p.file_name()
+                            .and_then(|n| n.to_str())
+                            .map(|n| n.starts_with("session-"))
+                            .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Find the chat file for a Gemini session
+///
+/// Prefers the hook-published transcript path, then a chat whose
+/// `sessionId` matches, then the most recent chat for the working directory.
+pub fn locate_transcript(session: &SessionRef) -> Option<PathBuf> {
+    if let Some(ref path) = session.transcript_path {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let chats_dir = gemini_tmp_dir()?
+        .join(project_hash(&session.working_dir))
+        .join("chats");
+    let files = chat_files(&chats_dir);
+
+    if let Some(ref id) = session.session_id {
+        // File names embed the first 8 characters of the session id
+        let short_id: String = id.chars().take(8).collect();
+        let by_id = files.iter().find(|p| {
+            p.file_stem()
+                .and_then(|n| n.to_str())
+                .map(|n| n.ends_with(&short_id))
+                .unwrap_or(false)
+                && session_id_of(p).as_deref() == Some(id.as_str())
+        });
+        if let Some(path) = by_id {
+            return Some(path.clone());
+        }
+    }
+
+    files
+        .into_iter()
+        .filter_map(|p| {
+            fs::metadata(&p)
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .map(|t| (p, t))
+        })
+        .max_by_key(|(_, t)| *t)
+        .map(|(p, _)| p)
+}
+
+fn session_id_of(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    json.get("sessionId")?.as_str().map(|s| s.to_string())
+}
+
+/// Sum token usage over all messages of a chat file
+pub fn parse_chat_tokens(path: &Path) -> TokenUsage {
+    let mut usage = TokenUsage::default();
+
+    let json: serde_json::Value = match fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+    {
+        Some(j) => j,
+        None => return usage,
+    };
+
+    let messages = match json.get("messages").and_then(|m| m.as_array()) {
+        Some(m) => m,
+        None => return usage,
+    };
+
+    for message in messages {
+        let tokens = match message.get("tokens") {
+            Some(t) => t,
+            None => continue,
+        };
+        let get = |key: &str| tokens.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+
+        // `input` includes cached prompt tokens; thoughts are billed as output
+        let cached = get("cached");
+        let model = message
+            .get("model")
+            .and_then(|m| m.as_str())
+            .unwrap_or(UNKNOWN_MODEL);
+
+        usage.add(
+            model,
+            &ModelUsage {
+                input_tokens: get("input").saturating_sub(cached) + get("tool"),
+                output_tokens: get("output") + get("thoughts"),
+                cache_read_tokens: cached,
+                cache_write_5m_tokens: 0,
+                cache_write_1h_tokens: 0,
+            },
+        );
+    }
+
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_hash() {
+        assert_eq!(
+            project_hash(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(project_hash("/home/user/project").len(), 64);
+    }
+
+    #[test]
+    fn test_parse_chat_tokens() {
+        let path = std::env::temp_dir().join(format!("coder-tools-gemini-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{"sessionId":"abc","messages":[
+                {"type":"user","content":"hi"},
+                {"type":"gemini","model":"gemini-2.5-pro","tokens":{"input":1000,"output":200,"cached":400,"thoughts":50,"tool":10,"total":1260}},
+                {"type":"gemini","model":"gemini-2.5-flash","tokens":{"input":100,"output":20,"cached":0,"thoughts":0,"tool":0,"total":120}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let usage = parse_chat_tokens(&path);
+        assert_eq!(usage.input_tokens, 610 + 100);
+        assert_eq!(usage.output_tokens, 250 + 20);
+        assert_eq!(usage.cache_read_tokens, 400);
+        assert_eq!(usage.by_model.len(), 2);
+        assert_eq!(session_id_of(&path).as_deref(), Some("abc"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
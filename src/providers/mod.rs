@@ -0,0 +1,146 @@
+//! Supported terminal agents
+//!
+//! Everything that differs between agents sits behind `Provider`: how to
+//! recognise the agent in a pane, how to make it publish its status, where
+//! its transcripts live, how they record token usage, and how to show it.
+//! Users can declare further agents under `agents` in the config.
+
+mod aider;
+pub mod claude;
+pub mod codex;
+pub mod custom;
+pub mod gemini;
+
+use crate::config;
+use crate::cost::{SessionRef, UsageFormat};
+use crate::hooks::HookConfig;
+use crate::mux::Pane;
+use crate::usage_index::IndexKind;
+use custom::CustomAgent;
+use ratatui::style::Color;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub trait Provider: Sync {
+    /// Identifier published by hooks as @agent_provider
+    fn name(&self) -> &'static str;
+
+    /// Name shown in notifications and messages
+    fn display_name(&self) -> &'static str;
+
+    /// Badge color in the monitor
+    fn color(&self) -> Color;
+
+    /// Whether `pane_current_command` looks like this agent's process
+    fn is_running(&self, command: &str) -> bool;
+
+    /// Settings file where its status hooks are installed, if it has one
+    fn hooks(&self) -> Option<&'static dyn HookConfig> {
+        None
+    }
+
+    /// Transcript for a pane's session, if the agent keeps one
+    fn locate_transcript(&self, _session: &SessionRef) -> Option<PathBuf> {
+        None
+    }
+
+    /// How token usage is read from transcripts, if it is recorded at all
+    fn usage_format(&self) -> Option<UsageFormat> {
+        None
+    }
+
+    /// Transcripts of subagents working for a session, whose usage counts
+    /// towards it
+    fn subagent_transcripts(&self, _transcript: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// How transcripts are kept in the usage index; those without one are
+    /// read directly every time
+    fn index_kind(&self) -> Option<IndexKind> {
+        None
+    }
+}
+
+static BUILTIN: &[&dyn Provider] = &[
+    &claude::Claude,
+    &gemini::Gemini,
+    &codex::Codex,
+    &aider::Aider,
+];
+
+/// Built-in providers followed by the agents declared in the config
+struct Registry {
+    providers: Vec<&'static dyn Provider>,
+    custom: Vec<&'static CustomAgent>,
+    /// Declared agents that were skipped, with the reason
+    errors: Vec<String>,
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry {
+            providers: BUILTIN.to_vec(),
+            custom: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let agents = config::shared().map(|c| c.agents.as_slice()).unwrap_or_default();
+        for agent in agents {
+            match CustomAgent::from_config(agent) {
+                Ok(_) if registry.providers.iter().any(|p| p.name() == agent.name.trim()) => {
+                    registry
+                        .errors
+                        .push(format!("{}: an agent with this name already exists", agent.name));
+                }
+                Ok(custom) => {
+                    let custom: &'static CustomAgent = Box::leak(Box::new(custom));
+                    registry.providers.push(custom);
+                    registry.custom.push(custom);
+                }
+                Err(e) => registry.errors.push(format!("{:#}", e)),
+            }
+        }
+        registry
+    })
+}
+
+pub fn all() -> &'static [&'static dyn Provider] {
+    &registry().providers
+}
+
+/// Look up a provider by its @agent_provider name
+pub fn get(name: &str) -> Option<&'static dyn Provider> {
+    all().iter().copied().find(|p| p.name() == name)
+}
+
+/// Problems with agents declared in the config, for reporting at startup
+pub fn config_errors() -> &'static [String] {
+    &registry().errors
+}
+
+/// Attribute panes running a declared agent, unless a running agent already
+/// published itself through hooks
+pub fn detect_custom_agents(panes: &mut [Pane]) {
+    let custom = &registry().custom;
+    if custom.is_empty() {
+        return;
+    }
+
+    for pane in panes {
+        let published = pane
+            .agent_provider
+            .as_deref()
+            .and_then(get)
+            .map(|p| p.is_running(&pane.current_command))
+            .unwrap_or(false);
+        if published {
+            continue;
+        }
+
+        if let Some(agent) = custom.iter().find(|a| a.is_running(&pane.current_command)) {
+            agent.detect(pane);
+        }
+    }
+}
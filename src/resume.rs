@@ -1,12 +1,16 @@
+use crate::cost::{self, TokenUsage};
+use crate::profile::{self, Profile};
+use crate::providers::{self, codex};
+use crate::usage_index;
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Subcommand, Debug)]
 pub enum ResumeAction {
-    /// List recent Claude Code sessions
+    /// List recent Claude Code and Codex sessions
     List {
         /// Number of sessions to show
         #[arg(short, long, default_value = "10")]
@@ -39,6 +43,9 @@ struct MessageContent {
 
 #[derive(Debug)]
 pub struct Session {
+    pub provider: &'static str,
+    /// Claude Code profile the transcript belongs to
+    pub profile: Option<&'static Profile>,
     pub id: String,
     pub path: PathBuf,
     pub project_path: String,
@@ -48,27 +55,47 @@ pub struct Session {
 }
 
 pub fn run(action: ResumeAction) -> Result<()> {
-    match action {
+    let result = match action {
         ResumeAction::List { limit } => list_sessions(limit),
         ResumeAction::Show { session } => show_session(&session),
         ResumeAction::Open { session } => open_session(&session),
-    }
+    };
+    // The index is only a cache; failing to write it just makes the next run slower
+    let _ = usage_index::save();
+    result
 }
 
-fn get_claude_projects_dir() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".claude").join("projects"))
+fn find_sessions(limit: usize) -> Result<Vec<Session>> {
+    let mut sessions: Vec<Session> = Vec::new();
+
+    for profile in profile::active() {
+        find_claude_sessions(profile, &mut sessions)?;
+    }
+
+    // Codex sessions belong to no profile, so --profile leaves them out
+    if profile::selected().is_none() {
+        // Rollouts are already newest first, so only the first `limit` can make the cut
+        for (path, modified) in codex::rollout_files().into_iter().take(limit) {
+            if let Some(session) = parse_codex_session(&path, modified) {
+                sessions.push(session);
+            }
+        }
+    }
+
+    // Sort by modification time (newest first)
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.modified));
+    sessions.truncate(limit);
+
+    Ok(sessions)
 }
 
-fn find_sessions(limit: usize) -> Result<Vec<Session>> {
-    let projects_dir = get_claude_projects_dir()?;
+fn find_claude_sessions(profile: &'static Profile, sessions: &mut Vec<Session>) -> Result<()> {
+    let projects_dir = profile.projects_dir();
 
     if !projects_dir.exists() {
-        return Ok(Vec::new());
+        return Ok(());
     }
 
-    let mut sessions: Vec<Session> = Vec::new();
-
     // Walk through project directories
     for entry in fs::read_dir(&projects_dir)? {
         let entry = entry?;
@@ -84,61 +111,27 @@ fn find_sessions(limit: usize) -> Result<Vec<Session>> {
             let file_path = file_entry.path();
 
             if file_path.extension().map(|e| e == "jsonl").unwrap_or(false) {
-                if let Ok(session) = parse_session_file(&file_path) {
+                if let Ok(mut session) = parse_session_file(&file_path) {
+                    session.profile = Some(profile);
                     sessions.push(session);
                 }
             }
         }
     }
 
-    // Sort by modification time (newest first)
-    sessions.sort_by(|a, b| b.modified.cmp(&a.modified));
-    sessions.truncate(limit);
-
-    Ok(sessions)
+    Ok(())
 }
 
 fn parse_session_file(path: &PathBuf) -> Result<Session> {
     let metadata = fs::metadata(path)?;
     let modified = metadata.modified()?;
 
-    let content = fs::read_to_string(path)?;
-    let lines: Vec<&str> = content.lines().collect();
-
-    let mut message_count = 0;
-    let mut last_prompt = None;
-    let mut project_path = String::new();
-
-    for line in &lines {
-        if let Ok(msg) = serde_json::from_str::<SessionMessage>(line) {
-            message_count += 1;
-
-            // Extract project path from first message if available
-            if project_path.is_empty() {
-                if let Some(ref content) = msg.message {
-                    if content.role.as_deref() == Some("user") {
-                        // Try to extract cwd from the message
-                        if let Some(serde_json::Value::String(s)) = &content.content {
-                            if s.contains("cwd:") {
-                                if let Some(cwd) = s.split("cwd:").nth(1) {
-                                    project_path = cwd.trim().split('\n').next().unwrap_or("").to_string();
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            // Get last user prompt
-            if let Some(ref content) = msg.message {
-                if content.role.as_deref() == Some("user") {
-                    if let Some(serde_json::Value::String(s)) = &content.content {
-                        last_prompt = Some(s.chars().take(80).collect());
-                    }
-                }
-            }
-        }
-    }
+    // Counted once and then kept up to date by the usage index
+    let summary = usage_index::with_index(|index| index.session_summary(path))
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let message_count = summary.messages;
+    let last_prompt = summary.last_prompt;
+    let mut project_path = summary.project_path.unwrap_or_default();
 
     // Use parent directory name as project path if not found
     if project_path.is_empty() {
@@ -156,6 +149,8 @@ fn parse_session_file(path: &PathBuf) -> Result<Session> {
         .to_string();
 
     Ok(Session {
+        provider: "claude",
+        profile: None,
         id,
         path: path.clone(),
         project_path,
@@ -165,25 +160,58 @@ fn parse_session_file(path: &PathBuf) -> Result<Session> {
     })
 }
 
+fn parse_codex_session(path: &Path, modified: std::time::SystemTime) -> Option<Session> {
+    let meta = codex::read_session_meta(path)?;
+    let messages = codex::rollout_messages(path);
+
+    let last_prompt = messages
+        .iter()
+        .rev()
+        .find(|(role, _)| role == "user")
+        .map(|(_, text)| text.chars().take(80).collect());
+
+    Some(Session {
+        provider: "codex",
+        profile: None,
+        id: meta.id,
+        path: path.to_path_buf(),
+        project_path: meta.cwd,
+        modified,
+        message_count: messages.len(),
+        last_prompt,
+    })
+}
+
+fn session_usage(session: &Session) -> TokenUsage {
+    let provider = match providers::get(session.provider) {
+        Some(p) => p,
+        None => return TokenUsage::default(),
+    };
+    usage_index::session_usage(provider, &session.path)
+        .or_else(|| provider.usage_format().map(|format| format.parse_file(&session.path)))
+        .unwrap_or_default()
+}
+
 fn list_sessions(limit: usize) -> Result<()> {
     let sessions = find_sessions(limit)?;
 
     if sessions.is_empty() {
-        println!("No Claude Code sessions found.");
-        println!("Sessions are stored in ~/.claude/projects/");
+        println!("No Claude Code or Codex sessions found.");
+        println!("Sessions are stored in ~/.claude/projects/ and ~/.codex/sessions/");
         return Ok(());
     }
 
-    println!("{:<4} {:<20} {:<30} {}", "#", "Project", "Last Modified", "Messages");
-    println!("{}", "-".repeat(70));
+    println!("{:<4} {:<16} {:<20} {:<30} Messages", "#", "Agent", "Project", "Last Modified");
+    println!("{}", "-".repeat(87));
 
     for (i, session) in sessions.iter().enumerate() {
         let modified = format_time(session.modified);
         let project = session.project_path.chars().take(20).collect::<String>();
 
         println!(
-            "{:<4} {:<20} {:<30} {}",
+            "{:<4} {:<16} {:<20} {:<30} {}",
             i + 1,
+            agent_label(session),
             project,
             modified,
             session.message_count
@@ -205,42 +233,67 @@ fn show_session(session_ref: &str) -> Result<()> {
     let session = resolve_session(session_ref)?;
 
     println!("Session: {}", session.id);
+    println!("Agent: {}", session.provider);
+    if let Some(profile) = session.profile {
+        println!("Profile: {} ({})", profile.name, profile.dir.display());
+    }
     println!("Project: {}", session.project_path);
     println!("Path: {}", session.path.display());
     println!("Messages: {}", session.message_count);
+
+    let usage = session_usage(&session);
+    if usage.total_tokens() > 0 {
+        println!(
+            "Tokens: {} in, {} out, {} cached ({})",
+            cost::format_tokens(usage.input_tokens),
+            cost::format_tokens(usage.output_tokens),
+            cost::format_tokens(usage.cache_read_tokens),
+            cost::format_cost(usage.cost_usd())
+        );
+    }
     println!();
 
     // Show last few messages
-    let content = fs::read_to_string(&session.path)?;
-    let lines: Vec<&str> = content.lines().collect();
+    let messages = match session.provider {
+        "codex" => codex::rollout_messages(&session.path),
+        _ => claude_messages(&session.path)?,
+    };
 
     println!("Recent messages:");
     println!("{}", "-".repeat(60));
 
-    for line in lines.iter().rev().take(10).rev() {
-        if let Ok(msg) = serde_json::from_str::<SessionMessage>(line) {
-            if let Some(ref content) = msg.message {
-                let role = content.role.as_deref().unwrap_or("?");
-                let text = match &content.content {
-                    Some(serde_json::Value::String(s)) => s.chars().take(100).collect::<String>(),
-                    Some(v) => format!("{:?}", v).chars().take(100).collect(),
-                    None => "(no content)".to_string(),
-                };
-
-                let role_display = match role {
-                    "user" => "\x1b[32muser\x1b[0m",
-                    "assistant" => "\x1b[34massistant\x1b[0m",
-                    _ => role,
-                };
-
-                println!("[{}] {}", role_display, text);
-            }
-        }
+    for (role, text) in messages.iter().rev().take(10).rev() {
+        let role_display = match role.as_str() {
+            "user" => "\x1b[32muser\x1b[0m",
+            "assistant" => "\x1b[34massistant\x1b[0m",
+            _ => role,
+        };
+
+        println!("[{}] {}", role_display, text.chars().take(100).collect::<String>());
     }
 
     Ok(())
 }
 
+fn claude_messages(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path)?;
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<SessionMessage>(line).ok())
+        .filter_map(|msg| msg.message)
+        .map(|content| {
+            let role = content.role.unwrap_or_else(|| "?".to_string());
+            let text = match content.content {
+                Some(serde_json::Value::String(s)) => s,
+                Some(v) => format!("{:?}", v),
+                None => "(no content)".to_string(),
+            };
+            (role, text)
+        })
+        .collect())
+}
+
 fn open_session(session_ref: &str) -> Result<()> {
     let session = resolve_session(session_ref)?;
 
@@ -255,7 +308,7 @@ fn open_session(session_ref: &str) -> Result<()> {
     };
 
     println!("Opening: {}", project_dir);
-    println!("Run 'claude' to start a new session in this project.");
+    println!("Run '{}' to continue this session.", resume_command(&session));
 
     // Open a new terminal or print cd command
     println!();
@@ -264,6 +317,26 @@ fn open_session(session_ref: &str) -> Result<()> {
     Ok(())
 }
 
+fn resume_command(session: &Session) -> String {
+    match (session.provider, session.profile) {
+        ("codex", _) => format!("codex resume {}", session.id),
+        (_, Some(profile)) if profile.needs_env() => format!(
+            "CLAUDE_CONFIG_DIR=\"{}\" claude --resume {}",
+            profile.dir.display(),
+            session.id
+        ),
+        _ => format!("claude --resume {}", session.id),
+    }
+}
+
+/// Agent column: the provider, with the profile when several are configured
+fn agent_label(session: &Session) -> String {
+    match session.profile {
+        Some(profile) if profile::has_several() => format!("{}:{}", session.provider, profile.name),
+        _ => session.provider.to_string(),
+    }
+}
+
 fn resolve_session(session_ref: &str) -> Result<Session> {
     let sessions = find_sessions(100)?;
 
@@ -310,6 +383,8 @@ fn format_time(time: std::time::SystemTime) -> String {
 impl Clone for Session {
     fn clone(&self) -> Self {
         Session {
+            provider: self.provider,
+            profile: self.profile,
             id: self.id.clone(),
             path: self.path.clone(),
             project_path: self.project_path.clone(),
@@ -0,0 +1,291 @@
+//! Agent status outside tmux
+//!
+//! Alongside the tmux pane options, the hook handler keeps one small JSON
+//! file per agent process in a state directory. The monitor lists those
+//! files so agents in plain terminals, IDEs, zellij or screen show up too;
+//! entries whose process has exited are removed as they are found.
+
+use crate::mux::{self, Pane};
+use crate::providers;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Session name shown for agents that aren't in a tmux pane
+pub const DETACHED_SESSION: &str = "(no tmux)";
+
+/// Shells that may sit between an agent and the hook it runs
+#[cfg(unix)]
+const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "fish"];
+
+/// Contents of a state file; keys mirror the @agent_* pane options
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AgentState {
+    pid: u32,
+    provider: Option<String>,
+    status: Option<String>,
+    task: Option<String>,
+    session_id: Option<String>,
+    transcript: Option<String>,
+    cwd: Option<String>,
+    tool: Option<String>,
+    profile: Option<String>,
+    /// Multiplexer the agent runs in, if any
+    mux: Option<String>,
+    /// The agent's pane id in that multiplexer
+    mux_pane: Option<String>,
+    /// File the state was read from
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// ~/.local/state/coder-tools/agents (or the platform's local data dir)
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|d| d.join("coder-tools").join("agents"))
+}
+
+/// Merge pane options (`@agent_status`, ...) into the agent's state file
+pub fn publish(provider: &str, options: &[(&str, String)]) -> Result<()> {
+    let dir = state_dir().context("Could not determine state directory")?;
+    let pid = agent_pid();
+    let path = dir.join(format!("{}-{}.json", provider, pid));
+
+    // Fields an event doesn't mention keep their earlier values
+    let mut state: Map<String, Value> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    for (name, value) in options {
+        let key = name.trim_start_matches("@agent_");
+        state.insert(key.to_string(), Value::String(value.clone()));
+    }
+    state.insert("pid".to_string(), pid.into());
+    match mux::inside().and_then(|m| Some((m.name(), m.current_pane_id()?))) {
+        Some((name, pane)) => {
+            state.insert("mux".to_string(), name.into());
+            state.insert("mux_pane".to_string(), pane.into());
+        }
+        None => {
+            state.remove("mux");
+            state.remove("mux_pane");
+        }
+    }
+
+    // Write then rename, so the monitor never reads half a file
+    fs::create_dir_all(&dir)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string(&state)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Multiplexer panes plus agents known only from their state files
+///
+/// Without a running multiplexer the listing still succeeds if any agent
+/// has published state.
+pub fn list_panes() -> Result<Vec<Pane>> {
+    let backend = mux::active();
+    let mut panes = match backend.list_panes() {
+        Ok(mut panes) => {
+            providers::detect_custom_agents(&mut panes);
+            panes
+        }
+        Err(e) => {
+            let mut panes = Vec::new();
+            add_agent_states(backend.name(), &mut panes);
+            return if panes.is_empty() { Err(e) } else { Ok(panes) };
+        }
+    };
+    add_agent_states(backend.name(), &mut panes);
+    Ok(panes)
+}
+
+/// Describe listed panes from their agents' state files, and add agents
+/// running outside the multiplexer as panes without a pane id
+fn add_agent_states(backend: &str, panes: &mut Vec<Pane>) {
+    for state in load_states() {
+        // A pid reused by another program fails the provider's command check
+        let command = match process_info(state.pid).map(|(_, command)| script_name(state.pid, command)) {
+            Some(command) if runs_agent(&state, &command) => command,
+            Some(_) => {
+                let _ = fs::remove_file(&state.path);
+                continue;
+            }
+            None => continue,
+        };
+        let listed = state
+            .mux_pane
+            .as_ref()
+            .filter(|_| state.mux.as_deref() == Some(backend))
+            .and_then(|id| panes.iter_mut().find(|p| &p.id == id));
+        match listed {
+            // Options published on the pane itself (tmux) take precedence
+            Some(pane) if pane.agent_provider.is_some() => {}
+            Some(pane) => describe(pane, state, command),
+            None => {
+                let mut pane = Pane::new(
+                    format!("pid:{}", state.pid),
+                    DETACHED_SESSION.to_string(),
+                    0,
+                    0,
+                    String::new(),
+                );
+                pane.agent_pid = Some(state.pid);
+                describe(&mut pane, state, command);
+                panes.push(pane);
+            }
+        }
+    }
+}
+
+/// Live agents' states, removing files of processes that have exited
+fn load_states() -> Vec<AgentState> {
+    let dir = match state_dir() {
+        Some(d) => d,
+        None => return Vec::new(),
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+        .filter_map(|path| {
+            let mut state: AgentState = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+            if state.pid == 0 || !is_alive(state.pid) {
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+            state.path = path;
+            Some(state)
+        })
+        .collect()
+}
+
+/// Whether `command` is the agent the state file was written for
+fn runs_agent(state: &AgentState, command: &str) -> bool {
+    state
+        .provider
+        .as_deref()
+        .and_then(providers::get)
+        .map(|provider| provider.is_running(command))
+        .unwrap_or(false)
+}
+
+/// Fill in a pane's agent details from its state file, as hooks would in tmux
+fn describe(pane: &mut Pane, state: AgentState, command: String) {
+    let field = |value: Option<String>| value.filter(|v| !v.is_empty());
+    if pane.current_path.is_empty() {
+        pane.current_path = state.cwd.clone().unwrap_or_default();
+    }
+    pane.current_command = command;
+    pane.agent_provider = field(state.provider);
+    pane.agent_status = field(state.status);
+    pane.agent_task = field(state.task);
+    pane.agent_session_id = field(state.session_id);
+    pane.agent_transcript = field(state.transcript);
+    pane.agent_cwd = field(state.cwd);
+    pane.agent_tool = field(state.tool);
+    pane.agent_profile = field(state.profile);
+}
+
+/// The agent that ran this hook: our parent, skipping a shell in between
+#[cfg(unix)]
+fn agent_pid() -> u32 {
+    let mut pid = std::os::unix::process::parent_id();
+    for _ in 0..2 {
+        match process_info(pid) {
+            Some((parent, command)) if SHELLS.contains(&command.as_str()) && parent > 1 => pid = parent,
+            _ => break,
+        }
+    }
+    pid
+}
+
+/// Without a parent pid to go by, files are keyed by the hook's own pid
+#[cfg(not(unix))]
+fn agent_pid() -> u32 {
+    std::process::id()
+}
+
+/// Parent pid and command name of a running process
+fn process_info(pid: u32) -> Option<(u32, String)> {
+    let output = Command::new("ps")
+        .args(["-o", "ppid=,comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (ppid, command) = text.trim().split_once(char::is_whitespace)?;
+    // macOS reports the full path; tmux and Linux just the name
+    let command = command.trim().rsplit('/').next().unwrap_or("").trim_start_matches('-');
+    Some((ppid.trim().parse().ok()?, command.to_string()))
+}
+
+/// A Python process named by the script it runs, as panes are (see `mux`)
+fn script_name(pid: u32, command: String) -> String {
+    if !command.to_ascii_lowercase().starts_with("python") {
+        return command;
+    }
+    Command::new("ps")
+        .args(["-o", "args=", "-p", &pid.to_string()])
+        .output()
+        .ok()
+        .map(|output| mux::command_name(String::from_utf8_lossy(&output.stdout).trim()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(command)
+}
+
+fn is_alive(pid: u32) -> bool {
+    if Path::new("/proc").is_dir() {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+    process_info(pid).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent_state(json: &str) -> AgentState {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_describe_pane_from_state() {
+        let state = agent_state(
+            r#"{"pid": 4242, "provider": "claude", "status": "waiting", "tool": "", "cwd": "/home/u/proj",
+                "mux": "screen", "mux_pane": "77.work:1"}"#,
+        );
+        assert_eq!(state.mux_pane.as_deref(), Some("77.work:1"));
+
+        let mut pane = Pane::new("77.work:1".to_string(), "work".to_string(), 1, 0, "bash".to_string());
+        describe(&mut pane, state, "claude".to_string());
+
+        assert!(pane.in_multiplexer());
+        assert_eq!(pane.display_name(), "work:1.0");
+        assert_eq!(pane.current_command, "claude");
+        assert_eq!(pane.agent_status.as_deref(), Some("waiting"));
+        assert_eq!(pane.agent_tool, None);
+        assert_eq!(pane.current_path, "/home/u/proj");
+    }
+
+    #[test]
+    fn test_reused_pid_is_not_the_agent() {
+        let state = agent_state(r#"{"pid": 4242, "provider": "codex"}"#);
+        assert!(runs_agent(&state, "codex"));
+        assert!(!runs_agent(&state, "vim"));
+        assert!(!runs_agent(&agent_state(r#"{"pid": 4242}"#), "codex"));
+    }
+}
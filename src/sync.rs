@@ -1,3 +1,4 @@
+use crate::profile;
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use std::fs;
@@ -7,7 +8,7 @@ use std::path::PathBuf;
 pub enum SyncAction {
     /// Push CLAUDE.md from source to target projects
     Push {
-        /// Source CLAUDE.md file or directory
+        /// Source CLAUDE.md file or directory (default: the profile's CLAUDE.md)
         #[arg(short, long)]
         source: Option<PathBuf>,
 
@@ -40,9 +41,9 @@ pub enum SyncAction {
 
     /// Initialize a master CLAUDE.md template
     Init {
-        /// Output path
-        #[arg(short, long, default_value = "~/.claude/CLAUDE.md")]
-        output: String,
+        /// Output path (default: CLAUDE.md in the profile's config dir)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 }
 
@@ -53,19 +54,19 @@ pub fn run(action: SyncAction) -> Result<()> {
         }
         SyncAction::Status { paths } => show_status(paths),
         SyncAction::Diff { source, target } => show_diff(&source, &target),
-        SyncAction::Init { output } => init_template(&output),
+        SyncAction::Init { output } => init_template(output),
     }
 }
 
+/// The user-level CLAUDE.md of the profile in use (~/.claude/CLAUDE.md)
 fn get_default_source() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME not set")?;
-    Ok(PathBuf::from(home).join(".claude").join("CLAUDE.md"))
+    Ok(profile::single()?.dir.join("CLAUDE.md"))
 }
 
 fn expand_path(path: &str) -> PathBuf {
-    if path.starts_with("~/") {
+    if let Some(rest) = path.strip_prefix("~/") {
         if let Ok(home) = std::env::var("HOME") {
-            return PathBuf::from(home).join(&path[2..]);
+            return PathBuf::from(home).join(rest);
         }
     }
     PathBuf::from(path)
@@ -98,7 +99,10 @@ fn push_claude_md(
     strategy: &str,
     dry_run: bool,
 ) -> Result<()> {
-    let source_path = source.unwrap_or_else(|| get_default_source().unwrap_or_default());
+    let source_path = match source {
+        Some(source) => source,
+        None => get_default_source()?,
+    };
 
     if !source_path.exists() {
         anyhow::bail!("Source file not found: {}", source_path.display());
@@ -190,7 +194,7 @@ fn show_status(paths: Vec<String>) -> Result<()> {
         paths
     };
 
-    println!("{:<40} {:<10} {}", "Project", "Status", "Match");
+    println!("{:<40} {:<10} Match", "Project", "Status");
     println!("{}", "-".repeat(70));
 
     for pattern in &search_paths {
@@ -255,8 +259,8 @@ fn show_diff(source: &PathBuf, target: &PathBuf) -> Result<()> {
     let max_lines = source_lines.len().max(target_lines.len());
 
     for i in 0..max_lines {
-        let source_line = source_lines.get(i).map(|s| *s);
-        let target_line = target_lines.get(i).map(|s| *s);
+        let source_line = source_lines.get(i).copied();
+        let target_line = target_lines.get(i).copied();
 
         match (source_line, target_line) {
             (Some(s), Some(t)) if s == t => {
@@ -279,8 +283,11 @@ fn show_diff(source: &PathBuf, target: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn init_template(output: &str) -> Result<()> {
-    let output_path = expand_path(output);
+fn init_template(output: Option<String>) -> Result<()> {
+    let output_path = match output {
+        Some(output) => expand_path(&output),
+        None => get_default_source()?,
+    };
 
     if output_path.exists() {
         anyhow::bail!("File already exists: {}", output_path.display());
@@ -1,6 +1,9 @@
 use crate::app::App;
+use crate::budget::Gauge;
 use crate::cost;
 use crate::detector::Status;
+use crate::profile;
+use crate::providers;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -25,13 +28,13 @@ pub fn render(frame: &mut Frame, app: &App) {
     } else {
         render_pane_list(frame, app, chunks[1]);
     }
-    render_footer(frame, chunks[2]);
+    render_footer(frame, app, chunks[2]);
 }
 
 fn render_header(frame: &mut Frame, app: &App, area: Rect) {
     let summary = app.summary();
 
-    let title = vec![
+    let mut title = vec![
         Span::styled(
             " Agent Monitor ",
             Style::default()
@@ -44,6 +47,10 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(Color::White),
         ),
     ];
+    for gauge in &app.budget_gauges {
+        title.push(Span::raw(" | "));
+        title.extend(budget_gauge(gauge));
+    }
 
     let mut status_line = vec![
         Span::raw(" "),
@@ -66,6 +73,28 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(header, area);
 }
 
+/// Width of a budget gauge's bar, in cells
+const GAUGE_WIDTH: usize = 10;
+
+/// "Daily ██████░░░░ 62% 1.2M/2.0M tokens", yellow from 80% and red over the limit
+fn budget_gauge(gauge: &Gauge) -> Vec<Span<'static>> {
+    let color = if gauge.percent >= 100.0 {
+        Color::Red
+    } else if gauge.percent >= 80.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    let filled = ((gauge.percent / 100.0 * GAUGE_WIDTH as f64).round() as usize).min(GAUGE_WIDTH);
+    vec![
+        Span::styled(format!("{} ", gauge.label), Style::default().fg(Color::White)),
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled("░".repeat(GAUGE_WIDTH - filled), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!(" {:.0}% ", gauge.percent), Style::default().fg(color)),
+        Span::styled(gauge.amounts.clone(), Style::default().fg(Color::DarkGray)),
+    ]
+}
+
 fn status_badge(icon: &str, count: usize, color: Color, label: &str) -> Span<'static> {
     Span::styled(
         format!("{} {} {}", icon, count, label),
@@ -77,7 +106,9 @@ fn render_pane_list(frame: &mut Frame, app: &App, area: Rect) {
     let panes = app.visible_panes();
 
     if panes.is_empty() {
-        let message = if app.show_all_panes {
+        let message = if !app.panes_loaded {
+            "Loading panes…"
+        } else if app.show_all_panes {
             "No tmux panes found. Is tmux running?"
         } else {
             "No agent sessions found. Press 'a' to show all panes."
@@ -135,16 +166,16 @@ fn render_grouped_items(app: &App, panes: &[&crate::app::PaneState], selected_in
     let mut current_session: Option<String> = None;
 
     for (i, pane_state) in panes.iter().enumerate() {
-        let session = &pane_state.pane.session_name;
+        let session = &pane_state.group_label();
 
-        // Add session header if this is a new session
+        // Add session header if this is a new session (or profile and session)
         if current_session.as_ref() != Some(session) {
             current_session = Some(session.clone());
             let is_collapsed = app.collapsed_sessions.contains(session);
 
             // Count panes in this session
             let session_panes: Vec<_> = panes.iter()
-                .filter(|p| &p.pane.session_name == session)
+                .filter(|p| &p.group_label() == session)
                 .collect();
             let working = session_panes.iter().filter(|p| p.status.status == Status::Working).count();
             let waiting = session_panes.iter().filter(|p| p.status.status == Status::WaitingForInput).count();
@@ -204,18 +235,7 @@ fn render_grouped_items(app: &App, panes: &[&crate::app::PaneState], selected_in
 
         let (_, folder_name) = split_path(&pane_state.pane.current_path);
 
-        // Provider badge if present
-        let provider_span = if let Some(ref provider) = pane_state.pane.agent_provider {
-            let (label, color) = match provider.as_str() {
-                "claude" => ("claude", Color::Magenta),
-                "gemini" => ("gemini", Color::Blue),
-                "codex" => ("codex", Color::Green),
-                _ => (provider.as_str(), Color::DarkGray),
-            };
-            Span::styled(format!("[{}] ", label), Style::default().fg(color))
-        } else {
-            Span::raw("")
-        };
+        let provider_span = provider_span(pane_state);
 
         // Order: status → project_name → provider → pane_number (dimmed) → duration
         let spans = vec![
@@ -235,13 +255,14 @@ fn render_grouped_items(app: &App, panes: &[&crate::app::PaneState], selected_in
             Span::raw(" "),
             provider_span,
             Span::styled(
-                format!("{}.{}", pane_state.pane.window_index, pane_state.pane.pane_index),
+                pane_state.pane.short_name(),
                 Style::default().fg(Color::DarkGray),
             ),
             Span::styled(
                 format!(" {}", pane_state.status_duration_str()),
                 Style::default().fg(Color::DarkGray),
             ),
+            loading_span(pane_state),
         ];
 
         let style = if is_selected {
@@ -274,18 +295,7 @@ fn render_compact_items(panes: &[&crate::app::PaneState], selected_index: usize)
             // Get just the folder name for compact view
             let (_, folder_name) = split_path(&pane_state.pane.current_path);
 
-            // Provider badge if present
-            let provider_span = if let Some(ref provider) = pane_state.pane.agent_provider {
-                let (label, color) = match provider.as_str() {
-                    "claude" => ("claude", Color::Magenta),
-                    "gemini" => ("gemini", Color::Blue),
-                    "codex" => ("codex", Color::Green),
-                    _ => (provider.as_str(), Color::DarkGray),
-                };
-                Span::styled(format!("[{}] ", label), Style::default().fg(color))
-            } else {
-                Span::raw("")
-            };
+            let provider_span = provider_span(pane_state);
 
             // Order: status → project_name → provider → pane_number (dimmed) → duration
             let spans = vec![
@@ -311,6 +321,7 @@ fn render_compact_items(panes: &[&crate::app::PaneState], selected_index: usize)
                     format!(" {}", pane_state.status_duration_str()),
                     Style::default().fg(Color::DarkGray),
                 ),
+                loading_span(pane_state),
             ];
 
             let style = if is_selected {
@@ -342,18 +353,7 @@ fn render_full_items(panes: &[&crate::app::PaneState], selected_index: usize) ->
             // Shorten path for display and split into parent + folder name
             let (parent_path, folder_name) = split_path(&pane_state.pane.current_path);
 
-            // Provider badge if present
-            let provider_span = if let Some(ref provider) = pane_state.pane.agent_provider {
-                let (label, color) = match provider.as_str() {
-                    "claude" => ("claude", Color::Magenta),
-                    "gemini" => ("gemini", Color::Blue),
-                    "codex" => ("codex", Color::Green),
-                    _ => (provider.as_str(), Color::DarkGray),
-                };
-                Span::styled(format!("[{}] ", label), Style::default().fg(color))
-            } else {
-                Span::raw("")
-            };
+            let provider_span = provider_span(pane_state);
 
             // Order: status → project_name → provider → pane_number (dimmed)
             let line1 = Line::from(vec![
@@ -391,8 +391,13 @@ fn render_full_items(panes: &[&crate::app::PaneState], selected_index: usize) ->
                 ),
             ];
 
-            // Add token/cost info if available (fetched with '$' key)
-            if let Some(ref tokens) = pane_state.tokens {
+            // Add token/cost info (kept up to date from the transcript)
+            if pane_state.tokens_loading && pane_state.tokens.is_none() {
+                line2_spans.push(Span::styled(
+                    "  loading tokens…",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            } else if let Some(ref tokens) = pane_state.tokens {
                 line2_spans.push(Span::styled(
                     format!(
                         "  {} tokens  {}",
@@ -401,12 +406,39 @@ fn render_full_items(panes: &[&crate::app::PaneState], selected_index: usize) ->
                     ),
                     Style::default().fg(Color::Yellow),
                 ));
+                if tokens.by_model.len() == 1 {
+                    if let Some(model) = tokens.by_model.keys().next() {
+                        line2_spans.push(Span::styled(
+                            format!("  [{}]", cost::short_model_name(model)),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                }
             }
 
             let line2 = Line::from(line2_spans);
 
             let mut lines = vec![line1, line2];
 
+            // Per-model breakdown when a session mixed models
+            if let Some(ref tokens) = pane_state.tokens {
+                if tokens.by_model.len() > 1 {
+                    let mut spans = vec![Span::raw("     ")];
+                    for (model, usage) in &tokens.by_model {
+                        spans.push(Span::styled(
+                            format!(
+                                "{} {} {}  ",
+                                cost::short_model_name(model),
+                                cost::format_tokens(usage.total_tokens()),
+                                cost::format_cost(usage.cost_usd(model))
+                            ),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    lines.push(Line::from(spans));
+                }
+            }
+
             // Add task (from @agent_task hook) if present
             if let Some(ref task) = pane_state.status.task {
                 let task_line = Line::from(vec![
@@ -420,6 +452,20 @@ fn render_full_items(panes: &[&crate::app::PaneState], selected_index: usize) ->
                 lines.push(task_line);
             }
 
+            // Tool being run or awaiting approval (from @agent_tool hook)
+            if let (Some(tool), Status::Working | Status::PermissionRequired) =
+                (&pane_state.pane.agent_tool, status)
+            {
+                lines.push(Line::from(vec![
+                    Span::raw("     "),
+                    Span::styled("⚙ ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        tool.chars().take(70).collect::<String>(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            }
+
             let style = if is_selected {
                 Style::default().bg(Color::DarkGray)
             } else {
@@ -431,12 +477,39 @@ fn render_full_items(panes: &[&crate::app::PaneState], selected_index: usize) ->
         .collect()
 }
 
+/// Provider badge if present, in the provider's color, with the profile
+/// when several are configured
+fn provider_span(pane_state: &crate::app::PaneState) -> Span<'static> {
+    match pane_state.pane.agent_provider {
+        Some(ref provider) => {
+            let color = providers::get(provider)
+                .map(|p| p.color())
+                .unwrap_or(Color::DarkGray);
+            let label = match pane_state.profile() {
+                Some(profile) if profile::has_several() => format!("{}:{}", provider, profile),
+                _ => provider.clone(),
+            };
+            Span::styled(format!("[{}] ", label), Style::default().fg(color))
+        }
+        None => Span::raw(""),
+    }
+}
+
+/// Marker shown in single-line views while token usage first loads
+fn loading_span(pane_state: &crate::app::PaneState) -> Span<'static> {
+    if pane_state.tokens_loading && pane_state.tokens.is_none() {
+        Span::styled(" …", Style::default().fg(Color::DarkGray))
+    } else {
+        Span::raw("")
+    }
+}
+
 fn render_stats(frame: &mut Frame, app: &App, area: Rect) {
     use crate::app::format_duration;
 
     let stats = app.aggregated_stats();
 
-    let lines = vec![
+    let mut lines = vec![
         Line::from(vec![
             Span::styled(" Aggregated Stats ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         ]),
@@ -485,6 +558,26 @@ fn render_stats(frame: &mut Frame, app: &App, area: Rect) {
         ]),
     ];
 
+    if !stats.by_model.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("  Cost by model:", Style::default().add_modifier(Modifier::BOLD)),
+        ]));
+        for (model, usage) in &stats.by_model {
+            lines.push(Line::from(vec![
+                Span::raw(format!("    {:<16}", cost::short_model_name(model))),
+                Span::styled(
+                    format!("{:>8} tokens  ", cost::format_tokens(usage.total_tokens())),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    cost::format_cost(usage.cost_usd(model)),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]));
+        }
+    }
+
     let stats_widget = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
@@ -495,7 +588,22 @@ fn render_stats(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(stats_widget, area);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
+fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(toast) = app.active_toast() {
+        let color = if toast.is_error { Color::Red } else { Color::Green };
+        let footer = Paragraph::new(Line::from(Span::styled(
+            format!(" {}", toast.message),
+            Style::default().fg(color),
+        )))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color)),
+        );
+        frame.render_widget(footer, area);
+        return;
+    }
+
     let help = Line::from(vec![
         Span::styled(" q ", Style::default().fg(Color::Yellow)),
         Span::raw("quit  "),
@@ -512,7 +620,9 @@ fn render_footer(frame: &mut Frame, area: Rect) {
         Span::styled(" g ", Style::default().fg(Color::Yellow)),
         Span::raw("group  "),
         Span::styled(" w/i ", Style::default().fg(Color::Yellow)),
-        Span::raw("filter"),
+        Span::raw("filter  "),
+        Span::styled(" e ", Style::default().fg(Color::Yellow)),
+        Span::raw("export"),
     ]);
 
     let footer = Paragraph::new(help).block(
@@ -0,0 +1,386 @@
+//! Persistent index of transcript usage
+//!
+//! Claude transcripts and Codex rollouts only ever grow, so their usage rows
+//! (one per message or `token_count` event) are kept in
+//! ~/.cache/coder-tools/usage-index.json together with each file's size,
+//! mtime and read offset, with the strings rows repeat (model, session,
+//! directory) stored once per file. Later runs only parse lines appended since, which
+//! keeps `budget`, `resume` and session costs fast however long the history
+//! gets. The index is only a cache: a missing, unreadable or outdated one is
+//! rebuilt from the transcripts.
+
+use crate::cost::{FileChange, FileCursor, ModelUsage, TokenUsage, UsageEntry};
+use crate::providers::{claude, codex::RolloutState, Provider};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Bumped whenever rows are read differently, so older indexes are rebuilt
+const INDEX_VERSION: u32 = 2;
+
+/// Transcript bytes read before frequent runs (the budget hook, the
+/// monitor's refreshes) write the whole index back
+pub const SAVE_AFTER_BYTES: u64 = 4 << 20;
+
+/// Characters of the latest prompt kept for `resume list`
+const PROMPT_CHARS: usize = 80;
+
+/// How a provider's transcript lines become usage rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    /// Each assistant line carries its own usage
+    Claude,
+    /// Cumulative `token_count` events, turned into deltas
+    Codex,
+}
+
+/// What `resume` lists about a Claude session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSummary {
+    /// JSON records in the transcript
+    pub messages: usize,
+    /// Start of the latest prompt typed by the user
+    pub last_prompt: Option<String>,
+    /// Directory named by a user message's `cwd:` line, if any
+    pub project_path: Option<String>,
+}
+
+impl SessionSummary {
+    fn add(&mut self, json: &serde_json::Value) {
+        if !json.is_object() {
+            return;
+        }
+        self.messages += 1;
+
+        let message = match json.get("message") {
+            Some(m) if m.get("role").and_then(|r| r.as_str()) == Some("user") => m,
+            _ => return,
+        };
+        if let Some(text) = message.get("content").and_then(|c| c.as_str()) {
+            if self.project_path.is_none() {
+                if let Some(cwd) = text.split("cwd:").nth(1) {
+                    self.project_path = Some(cwd.trim().split('\n').next().unwrap_or("").to_string());
+                }
+            }
+            self.last_prompt = Some(text.chars().take(PROMPT_CHARS).collect());
+        }
+    }
+}
+
+/// One transcript as far as it has been read
+#[derive(Default, Serialize, Deserialize)]
+struct IndexedFile {
+    /// How far the file has been read
+    #[serde(flatten)]
+    cursor: FileCursor,
+    /// Codex parser state, carried over to the lines appended next
+    #[serde(default)]
+    rollout: Option<RolloutState>,
+    #[serde(default)]
+    summary: SessionSummary,
+    #[serde(serialize_with = "store_rows", deserialize_with = "load_rows")]
+    rows: Vec<UsageEntry>,
+}
+
+/// A file's rows as written to disk
+#[derive(Default, Serialize, Deserialize)]
+struct StoredRows {
+    /// Models, sessions and directories, referred to by position
+    strings: Vec<String>,
+    rows: Vec<StoredRow>,
+}
+
+/// One row as an array: timestamp, model, usage (input, output, cache
+/// read, 5m and 1h cache writes), id, sidechain, session and directory
+#[derive(Serialize, Deserialize)]
+struct StoredRow(
+    Option<DateTime<Utc>>,
+    usize,
+    [u64; 5],
+    Option<String>,
+    bool,
+    Option<usize>,
+    Option<usize>,
+);
+
+fn store_rows<S: Serializer>(rows: &[UsageEntry], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let mut stored = StoredRows::default();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut position = |text: &str, strings: &mut Vec<String>| match positions.get(text) {
+        Some(position) => *position,
+        None => {
+            strings.push(text.to_string());
+            positions.insert(text.to_string(), strings.len() - 1);
+            strings.len() - 1
+        }
+    };
+    for row in rows {
+        let usage = &row.usage;
+        stored.rows.push(StoredRow(
+            row.timestamp,
+            position(&row.model, &mut stored.strings),
+            [
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_read_tokens,
+                usage.cache_write_5m_tokens,
+                usage.cache_write_1h_tokens,
+            ],
+            row.id.clone(),
+            row.sidechain,
+            row.session_id.as_deref().map(|s| position(s, &mut stored.strings)),
+            row.cwd.as_deref().map(|s| position(s, &mut stored.strings)),
+        ));
+    }
+    stored.serialize(serializer)
+}
+
+fn load_rows<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<UsageEntry>, D::Error> {
+    let stored = StoredRows::deserialize(deserializer)?;
+    let text = |position: usize| {
+        stored
+            .strings
+            .get(position)
+            .cloned()
+            .ok_or_else(|| serde::de::Error::custom("row refers to a missing string"))
+    };
+    stored
+        .rows
+        .iter()
+        .map(|StoredRow(timestamp, model, usage, id, sidechain, session, cwd)| {
+            Ok(UsageEntry {
+                timestamp: *timestamp,
+                model: text(*model)?,
+                usage: ModelUsage {
+                    input_tokens: usage[0],
+                    output_tokens: usage[1],
+                    cache_read_tokens: usage[2],
+                    cache_write_5m_tokens: usage[3],
+                    cache_write_1h_tokens: usage[4],
+                },
+                id: id.clone(),
+                sidechain: *sidechain,
+                session_id: session.map(text).transpose()?,
+                cwd: cwd.map(text).transpose()?,
+            })
+        })
+        .collect()
+}
+
+impl IndexedFile {
+    fn add_line(&mut self, kind: IndexKind, line: &str) {
+        let json: serde_json::Value = match serde_json::from_str(line) {
+            Ok(j) => j,
+            Err(_) => return,
+        };
+        let row = match kind {
+            IndexKind::Claude => {
+                self.summary.add(&json);
+                claude::usage_entry(&json)
+            }
+            IndexKind::Codex => self.rollout.get_or_insert_with(Default::default).entry(&json),
+        };
+        self.rows.extend(row);
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct UsageIndex {
+    version: u32,
+    files: HashMap<PathBuf, IndexedFile>,
+    /// Whether anything was read since loading, so there is something to save
+    #[serde(skip)]
+    dirty: bool,
+    /// Bytes of transcripts read since loading
+    #[serde(skip)]
+    read: u64,
+}
+
+impl UsageIndex {
+    fn load() -> Self {
+        index_path()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<UsageIndex>(&bytes).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_else(|| UsageIndex {
+                version: INDEX_VERSION,
+                ..Default::default()
+            })
+    }
+
+    /// Usage rows of a transcript, oldest first
+    pub fn entries(&mut self, path: &Path, kind: IndexKind) -> &[UsageEntry] {
+        match self.update(path, kind) {
+            Some(file) => &file.rows,
+            None => &[],
+        }
+    }
+
+    /// Message count and latest prompt of a Claude transcript
+    pub fn session_summary(&mut self, path: &Path) -> Option<SessionSummary> {
+        self.update(path, IndexKind::Claude).map(|file| file.summary.clone())
+    }
+
+    /// A transcript brought up to date, reading only newly appended lines
+    fn update(&mut self, path: &Path, kind: IndexKind) -> Option<&IndexedFile> {
+        let known = self.files.contains_key(path);
+        let file = self.files.entry(path.to_path_buf()).or_default();
+
+        match file.cursor.check(path) {
+            Ok(FileChange::Unchanged) => return self.files.get(path),
+            Ok(FileChange::Grown) => {}
+            Ok(FileChange::Rewritten) => *file = IndexedFile::default(),
+            Err(_) => {
+                self.files.remove(path);
+                self.dirty |= known;
+                return None;
+            }
+        }
+
+        let mut cursor = std::mem::take(&mut file.cursor);
+        let read = cursor.read_lines(path, |line| file.add_line(kind, line));
+        file.cursor = cursor;
+        if let Ok(consumed) = read {
+            self.read += consumed;
+            self.dirty = true;
+        }
+        self.files.get(path)
+    }
+}
+
+static INDEX: OnceLock<Mutex<UsageIndex>> = OnceLock::new();
+
+/// ~/.cache/coder-tools/usage-index.json (or the platform's cache dir)
+fn index_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("coder-tools").join("usage-index.json"))
+}
+
+/// Run `f` with the index, loading it from disk on first use
+pub fn with_index<T>(f: impl FnOnce(&mut UsageIndex) -> T) -> T {
+    let index = INDEX.get_or_init(|| Mutex::new(UsageIndex::load()));
+    let mut index = index.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut index)
+}
+
+/// Write the index back if anything was read into it, dropping transcripts
+/// that no longer exist
+pub fn save() -> Result<()> {
+    save_if_behind(0)
+}
+
+/// Like `save`, but only once at least `min_read` bytes of transcripts had
+/// to be read since loading, so that frequent short runs leave the index alone
+pub fn save_if_behind(min_read: u64) -> Result<()> {
+    let index = match INDEX.get() {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+    let mut index = index.lock().unwrap_or_else(|e| e.into_inner());
+    if !index.dirty || index.read < min_read {
+        return Ok(());
+    }
+    index.files.retain(|path, _| path.exists());
+
+    let path = index_path().context("Could not determine cache directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write then rename, so another run never reads half an index
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_vec(&*index)?)?;
+    fs::rename(&tmp, &path)?;
+    index.dirty = false;
+    index.read = 0;
+    Ok(())
+}
+
+/// Totals of a transcript's rows, counting each message once
+fn token_usage(rows: &[UsageEntry]) -> TokenUsage {
+    let mut seen = HashSet::new();
+    let mut usage = TokenUsage::default();
+    for row in rows {
+        if let Some(ref id) = row.id {
+            if !seen.insert(id) {
+                continue;
+            }
+        }
+        usage.add(&row.model, &row.usage);
+    }
+    usage
+}
+
+/// Usage of a session and its subagents, if the provider's transcripts are
+/// indexed
+pub fn session_usage(provider: &dyn Provider, transcript: &Path) -> Option<TokenUsage> {
+    let kind = provider.index_kind()?;
+    Some(with_index(|index| {
+        let mut usage = token_usage(index.entries(transcript, kind));
+        for subagent in provider.subagent_transcripts(transcript) {
+            usage.absorb(&token_usage(index.entries(&subagent, kind)));
+        }
+        usage
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_index_reads_appended_lines() {
+        let path = std::env::temp_dir().join(format!("coder-tools-index-{}.jsonl", std::process::id()));
+        let line = |id: &str, input: u64| {
+            format!(
+                "{{\"sessionId\":\"s1\",\"cwd\":\"/work/api\",\"message\":{{\"id\":\"{}\",\"role\":\"assistant\",\
+                 \"usage\":{{\"input_tokens\":{}}}}}}}\n",
+                id, input
+            )
+        };
+        fs::write(&path, line("a", 10)).unwrap();
+
+        let mut index = UsageIndex::default();
+        assert_eq!(index.entries(&path, IndexKind::Claude).len(), 1);
+
+        // Appended lines are added; a partial trailing line waits for its newline
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(line("a", 10).as_bytes()).unwrap();
+        file.write_all(b"{\"message\":{\"role\":\"user\",\"content\":\"hel").unwrap();
+        file.flush().unwrap();
+        let rows = index.entries(&path, IndexKind::Claude);
+        assert_eq!(rows.len(), 2);
+        // A streamed message repeated on two lines is counted once
+        assert_eq!(token_usage(rows).input_tokens, 10);
+        file.write_all(b"lo\"}}\n").unwrap();
+        file.flush().unwrap();
+        let summary = index.session_summary(&path).unwrap();
+        assert_eq!(summary.messages, 3);
+        assert_eq!(summary.last_prompt.as_deref(), Some("hello"));
+
+        // The index survives a round trip to disk, storing repeated strings once
+        let json = serde_json::to_string(&index).unwrap();
+        assert_eq!(json.matches("/work/api").count(), 1);
+        let mut index: UsageIndex = serde_json::from_str(&json).unwrap();
+        let rows = index.entries(&path, IndexKind::Claude);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].session_id.as_deref(), Some("s1"));
+        assert_eq!(rows[1].cwd.as_deref(), Some("/work/api"));
+        assert!(!index.dirty);
+
+        // Truncation starts the file over
+        fs::write(&path, line("b", 3)).unwrap();
+        assert_eq!(token_usage(index.entries(&path, IndexKind::Claude)).input_tokens, 3);
+
+        // So does a rewrite in place that leaves the file longer
+        fs::write(&path, line("c", 4) + &line("d", 6)).unwrap();
+        assert_eq!(token_usage(index.entries(&path, IndexKind::Claude)).input_tokens, 10);
+
+        fs::remove_file(&path).unwrap();
+        assert!(index.entries(&path, IndexKind::Claude).is_empty());
+        assert!(index.files.is_empty());
+    }
+}